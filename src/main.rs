@@ -1,4 +1,4 @@
-use std::{error::Error, io};
+use std::{env, error::Error, io::{self, IsTerminal}, process};
 
 use crossterm::{
     execute,
@@ -11,10 +11,40 @@ use tui::{
     backend::CrosstermBackend
 };
 
-mod typemaster;
-use typemaster::TypeMaster;
+use typemaster_cli::typemaster::{
+    TypeMaster,
+    config::{Config, Mode, MODE_FLAGS, USAGE},
+    text::read_text
+};
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let args : Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let mut config = match Config::from_args(args.iter().cloned()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+
+    for (_, err) in &config.wordlist_errors {
+        eprintln!("warning: {}, skipping it", err);
+    }
+
+    // text piped on stdin is used as a custom text, unless another mode was
+    // chosen or there is nothing to read
+    let mode_given = args.iter().any(|arg| MODE_FLAGS.contains(&arg.as_str()));
+    if !mode_given && !io::stdin().is_terminal() {
+        if let Ok(text) = read_text("-") {
+            config.text = Some(text);
+            config.mode = Mode::Text;
+        }
+    }
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -23,7 +53,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     // run app
-    let mut app = TypeMaster::new();
+    let mut app = TypeMaster::with_config(config);
     let res = app.run(&mut terminal);
 
     // restore terminal
@@ -0,0 +1,63 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Source of time for a typing session
+///
+/// The engine never reads the system time directly, so a [`ManualClock`] can be
+/// swapped in to step through a test without sleeping.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// Clock backed by the operating system's monotonic time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock that only moves when told to
+///
+/// Share it through an [`Rc`] to keep advancing it after handing it to a session.
+#[derive(Debug)]
+pub struct ManualClock {
+    base : Instant,
+    offset : Cell<Duration>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self { base: Instant::now(), offset: Cell::new(Duration::ZERO) }
+    }
+
+    pub fn advance(&self, duration : Duration) {
+        self.offset.set(self.offset.get() + duration);
+    }
+
+    /// Time passed since the clock was created
+    pub fn offset(&self) -> Duration {
+        self.offset.get()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.base + self.offset.get()
+    }
+}
+
+impl<C : Clock + ?Sized> Clock for Rc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
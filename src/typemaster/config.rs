@@ -0,0 +1,279 @@
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use super::session::{Difficulty, Limit, SubmitPolicy};
+use super::quotes::QuoteLength;
+use super::text::{CodeFile, Indent, read_text};
+use super::modifiers::Modifiers;
+use super::wordlist::{Wordlist, DEFAULT_LANGUAGE};
+
+/// Test durations offered in the menu (in seconds)
+pub const DURATION_PRESETS : [u64; 4] = [15, 30, 60, 120];
+/// Word counts offered in the menu
+pub const WORD_COUNT_PRESETS : [u64; 4] = [10, 25, 50, 100];
+/// Rates of numbers offered in the menu (in percent)
+pub const NUMBER_RATE_PRESETS : [u64; 5] = [0, 5, 10, 20, 30];
+
+/// Flags choosing the mode, in whose absence piped text is used
+pub const MODE_FLAGS : [&str; 12] = ["-t", "--time", "-w", "--words", "-q", "--quote", "-f", "--text", "-c", "--code", "-z", "--zen"];
+
+pub const USAGE : &str = "\
+Usage: typemaster-cli [OPTIONS]
+
+Options:
+  -t, --time <SECONDS>  Timed test of the given length (default: 60)
+  -w, --words <COUNT>   Test on a fixed number of words
+  -q, --quote <LENGTH>  Test on a quote: short, medium, long or any
+  -f, --text <PATH>     Test on the contents of a file, or stdin if PATH is '-' (used by default
+                        when text is piped and no other mode is chosen)
+  -c, --code <PATH>     Test on a source code file, typed line by line
+  -z, --zen             Free typing without target text, finished with Ctrl-D
+      --indent <MODE>   Leading indentation in code mode: auto or required (default: auto)
+  -l, --language <NAME> Language of generated words: english, spanish, french, german, russian,
+                        a user wordlist or the path of a wordlist file (default: english)
+  -p, --punctuation     Add capitals and punctuation to generated words
+  -n, --numbers <RATE>  Replace a percentage of generated words by numbers
+  -s, --submit <POLICY> Handling of mistyped words: strict, lenient or stop-on-letter (default: strict)
+  -d, --difficulty <LEVEL>
+                        Failing the test on mistakes: normal, expert or sudden-death (default: normal)
+      --no-backtrack    Don't allow backspacing into mistyped words with the lenient policy
+  -h, --help            Print this message
+
+Wordlists:
+  Files in typemaster/wordlists inside the configuration directory ($XDG_CONFIG_HOME,
+  ~/.config or %APPDATA%) can be chosen as languages named after the file. A '.txt'
+  wordlist has one word per line, a '.json' one is an array of words or an object
+  with \"words\" and an optional \"name\". Words must be unique and can't be empty
+  or contain whitespace.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Time,
+    Words,
+    Quote,
+    Text, // custom text given on the command line
+    Code, // source file given on the command line
+    Zen, // free typing, no target text
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Mode::Time => "time",
+            Mode::Words => "words",
+            Mode::Quote => "quote",
+            Mode::Text => "text",
+            Mode::Code => "code",
+            Mode::Zen => "zen",
+        })
+    }
+}
+
+/// Settings for the next test, chosen from the command line or the menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub mode : Mode,
+    pub duration : Duration,
+    pub word_count : usize,
+    pub quote_length : QuoteLength,
+    pub text : Option<String>, // contents of the custom text, if one was given
+    pub code : Option<CodeFile>, // source file for code mode, if one was given
+    pub indent : Indent,
+    pub modifiers : Modifiers, // applied to generated words in time and words modes
+    pub language : String, // name of the wordlist generated words are picked from
+    pub wordlists : Vec<Wordlist>, // languages that can be chosen
+    pub wordlist_errors : Vec<(String, String)>, // user wordlists that failed to load, by name
+    pub policy : SubmitPolicy,
+    pub difficulty : Difficulty,
+    pub backtrack : bool, // whether mistyped words can be reopened with backspace
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { mode: Mode::Time, duration: Duration::from_secs(60), word_count: 25, quote_length: QuoteLength::Any, text: None, code: None, indent: Indent::Auto, modifiers: Modifiers::default(), language: String::from(DEFAULT_LANGUAGE), wordlists: Wordlist::bundled(), wordlist_errors: vec![], policy: SubmitPolicy::Strict, difficulty: Difficulty::Normal, backtrack: true }
+    }
+}
+
+impl Config {
+    /// Parses command-line arguments, not including the program name
+    pub fn from_args<I : IntoIterator<Item = String>>(args : I) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+
+        // broken user wordlists are only an error if they are chosen
+        let (wordlists, errors) = Wordlist::load_user();
+        for wordlist in wordlists {
+            config.insert_wordlist(wordlist);
+        }
+        config.wordlist_errors = errors;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-t" | "--time" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    config.mode = Mode::Time;
+                    config.duration = Duration::from_secs(parse_positive(&arg, &value)?);
+                },
+                "-w" | "--words" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    config.mode = Mode::Words;
+                    config.word_count = parse_positive(&arg, &value)? as usize;
+                },
+                "-q" | "--quote" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    config.mode = Mode::Quote;
+                    config.quote_length = value.parse()?;
+                },
+                "-f" | "--text" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    config.mode = Mode::Text;
+                    config.text = Some(read_text(&value)?);
+                },
+                "-c" | "--code" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    config.mode = Mode::Code;
+                    config.code = Some(CodeFile { contents: read_text(&value)?, path: value });
+                },
+                "-z" | "--zen" => config.mode = Mode::Zen,
+                "-l" | "--language" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    config.language = config.add_language(&value)?;
+                },
+                "-p" | "--punctuation" => config.modifiers.punctuation = true,
+                "-n" | "--numbers" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    match value.parse::<u64>() {
+                        Ok(rate) if rate <= 100 => config.modifiers.number_rate = rate,
+                        _ => return Err(format!("invalid value '{}' for '{}': expected a percentage", value, arg)),
+                    }
+                },
+                "-s" | "--submit" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    config.policy = value.parse()?;
+                },
+                "-d" | "--difficulty" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    config.difficulty = value.parse()?;
+                },
+                "--no-backtrack" => config.backtrack = false,
+                "--indent" => {
+                    let value = args.next().ok_or(format!("missing value for '{}'", arg))?;
+                    config.indent = match value.as_str() {
+                        "auto" => Indent::Auto,
+                        "required" => Indent::Required,
+                        _ => return Err(format!("invalid value '{}' for '{}': expected auto or required", value, arg)),
+                    };
+                },
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Wordlist of the chosen language
+    pub fn wordlist(&self) -> &Wordlist {
+        self.wordlists.iter()
+            .find(|wordlist| wordlist.name == self.language)
+            .unwrap_or(&self.wordlists[0])
+    }
+
+    /// Names of the languages that can be chosen
+    pub fn languages(&self) -> Vec<&str> {
+        self.wordlists.iter().map(|wordlist| wordlist.name.as_str()).collect()
+    }
+
+    /// Makes a language available, loading it from disk unless it is a known
+    /// one, and returns its name
+    fn add_language(&mut self, language : &str) -> Result<String, String> {
+        if self.wordlists.iter().any(|wordlist| wordlist.name == language) {
+            return Ok(String::from(language));
+        }
+        if let Some((_, err)) = self.wordlist_errors.iter().find(|(name, _)| name == language) {
+            return Err(err.clone());
+        }
+        if !Path::new(language).is_file() {
+            return Err(format!("unknown language '{}': expected {} or the path of a wordlist", language, self.languages().join(", ")));
+        }
+
+        let wordlist = Wordlist::load(Path::new(language))?;
+        let name = wordlist.name.clone();
+        self.insert_wordlist(wordlist);
+        Ok(name)
+    }
+
+    /// Adds a wordlist, replacing the one with the same name if there is one
+    fn insert_wordlist(&mut self, wordlist : Wordlist) {
+        match self.wordlists.iter_mut().find(|known| known.name == wordlist.name) {
+            Some(known) => *known = wordlist,
+            None => self.wordlists.push(wordlist),
+        }
+    }
+
+    /// What ends a test started with these settings
+    pub fn limit(&self) -> Limit {
+        match self.mode {
+            Mode::Time => Limit::Time(self.duration),
+            Mode::Words => Limit::Words(self.word_count),
+            Mode::Quote | Mode::Text | Mode::Code => Limit::Text,
+            Mode::Zen => Limit::Zen,
+        }
+    }
+
+    /// Modes that can be selected, custom text and code only being available if they were given
+    pub fn available_modes(&self) -> Vec<Mode> {
+        let mut modes = vec![Mode::Time, Mode::Words, Mode::Quote];
+        if self.text.is_some() {
+            modes.push(Mode::Text);
+        }
+        if self.code.is_some() {
+            modes.push(Mode::Code);
+        }
+        modes.push(Mode::Zen);
+
+        modes
+    }
+
+    /// Short description of the test, as shown in the results
+    pub fn describe(&self) -> String {
+        let mut description = self.describe_mode();
+        if matches!(self.mode, Mode::Time | Mode::Words) {
+            if self.language != DEFAULT_LANGUAGE {
+                description.push_str(&format!(" {}", self.language));
+            }
+            if self.modifiers.punctuation {
+                description.push_str(" punctuation");
+            }
+            if self.modifiers.number_rate > 0 {
+                description.push_str(&format!(" numbers {}%", self.modifiers.number_rate));
+            }
+        }
+        if self.mode != Mode::Zen && self.policy != SubmitPolicy::Strict {
+            description.push_str(&format!(" {}", self.policy));
+        }
+        if self.mode != Mode::Zen && self.difficulty != Difficulty::Normal {
+            description.push_str(&format!(" {}", self.difficulty));
+        }
+
+        description
+    }
+
+    fn describe_mode(&self) -> String {
+        match self.mode {
+            Mode::Quote => format!("quote {}", self.quote_length),
+            Mode::Code => match &self.code {
+                Some(code) => format!("code {}", code.path),
+                None => String::from("code"),
+            },
+            _ => self.limit().to_string(),
+        }
+    }
+}
+
+fn parse_positive(arg : &str, value : &str) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("invalid value '{}' for '{}': expected a positive number", value, arg)),
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::Path;
+
+/// Languages understood by the built-in tokenizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    Python,
+    C,
+    JavaScript,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Plain,
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+const RUST_KEYWORDS : [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match",
+    "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static", "struct",
+    "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+const PYTHON_KEYWORDS : [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+    "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+    "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise",
+    "return", "try", "while", "with", "yield",
+];
+
+const C_KEYWORDS : [&str; 44] = [
+    "auto", "bool", "break", "case", "char", "class", "const", "continue", "default", "delete",
+    "do", "double", "else", "enum", "extern", "false", "float", "for", "goto", "if",
+    "inline", "int", "long", "namespace", "new", "nullptr", "private", "public", "register", "return",
+    "short", "signed", "sizeof", "static", "struct", "switch", "template", "this", "true", "typedef",
+    "union", "unsigned", "void", "while",
+];
+
+const JAVASCRIPT_KEYWORDS : [&str; 38] = [
+    "async", "await", "break", "case", "catch", "class", "const", "continue", "default", "delete",
+    "do", "else", "export", "extends", "false", "finally", "for", "from", "function", "if",
+    "import", "in", "instanceof", "interface", "let", "new", "null", "return", "static", "switch",
+    "this", "throw", "true", "try", "typeof", "undefined", "var", "while",
+];
+
+impl Language {
+    /// Guesses the language of a source file from its extension
+    pub fn detect(path : &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?;
+        match extension {
+            "rs" => Some(Language::Rust),
+            "py" => Some(Language::Python),
+            "c" | "h" | "cc" | "cpp" | "hpp" => Some(Language::C),
+            "js" | "jsx" | "ts" | "tsx" => Some(Language::JavaScript),
+            _ => None,
+        }
+    }
+
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &RUST_KEYWORDS,
+            Language::Python => &PYTHON_KEYWORDS,
+            Language::C => &C_KEYWORDS,
+            Language::JavaScript => &JAVASCRIPT_KEYWORDS,
+        }
+    }
+
+    fn line_comment(&self) -> &'static str {
+        match self {
+            Language::Python => "#",
+            _ => "//",
+        }
+    }
+
+    fn block_comments(&self) -> bool {
+        *self != Language::Python
+    }
+}
+
+/// Classifies every character of a line of code
+///
+/// Lines are tokenized on their own, so strings and block comments spanning
+/// several lines are only highlighted on the line they start.
+pub fn highlight(line : &str, language : Language) -> Vec<TokenKind> {
+    let chars : Vec<char> = line.chars().collect();
+    let mut kinds = vec![TokenKind::Plain; chars.len()];
+    let starts_with = |pos : usize, pattern : &str| pattern.chars().enumerate().all(|(i, c)| chars.get(pos + i) == Some(&c));
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        let start = pos;
+
+        let kind = if starts_with(pos, language.line_comment()) {
+            pos = chars.len();
+            TokenKind::Comment
+        } else if language.block_comments() && starts_with(pos, "/*") {
+            pos += 2;
+            while pos < chars.len() && !starts_with(pos, "*/") {
+                pos += 1;
+            }
+            pos = (pos + 2).min(chars.len());
+            TokenKind::Comment
+        } else if c == '"' || (c == '\'' && is_quote(&chars, pos, language)) {
+            pos += 1;
+            while pos < chars.len() && chars[pos] != c {
+                // skip escaped characters
+                pos += if chars[pos] == '\\' { 2 } else { 1 };
+            }
+            pos = (pos + 1).min(chars.len());
+            TokenKind::String
+        } else if c.is_ascii_digit() {
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_' || chars[pos] == '.') {
+                pos += 1;
+            }
+            TokenKind::Number
+        } else if c.is_alphabetic() || c == '_' {
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            let word : String = chars[start..pos].iter().collect();
+            if language.keywords().contains(&word.as_str()) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Plain
+            }
+        } else {
+            pos += 1;
+            TokenKind::Plain
+        };
+
+        kinds[start..pos].fill(kind);
+    }
+
+    kinds
+}
+
+/// Whether the single quote at `pos` opens a string, as opposed to a Rust lifetime
+fn is_quote(chars : &[char], pos : usize, language : Language) -> bool {
+    if language != Language::Rust {
+        return true;
+    }
+
+    // character literals are either 'c' or an escape like '\n'
+    match chars.get(pos + 1) {
+        Some('\\') => true,
+        Some(_) => chars.get(pos + 2) == Some(&'\''),
+        None => false,
+    }
+}
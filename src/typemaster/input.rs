@@ -0,0 +1,182 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Text typed for the current word, edited in grapheme clusters
+///
+/// The cursor counts graphemes rather than bytes, so it never lands inside a
+/// multi-byte character or splits a letter from its combining accents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputBuffer {
+    text : String,
+    cursor : usize, // graphemes before the cursor
+}
+
+impl InputBuffer {
+    /// Buffer holding `text`, with the cursor at its end
+    pub fn new(text : String) -> Self {
+        let cursor = text.graphemes(true).count();
+        Self { text, cursor }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Number of graphemes
+    pub fn len(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn grapheme(&self, pos : usize) -> Option<&str> {
+        self.text.graphemes(true).nth(pos)
+    }
+
+    /// Text before and after the cursor
+    pub fn split_at_cursor(&self) -> (&str, &str) {
+        self.text.split_at(self.byte_offset(self.cursor))
+    }
+
+    /// Inserts a character at the cursor, moving the cursor past it
+    ///
+    /// A combining character joins the grapheme before it, in which case the
+    /// cursor stays where it was. The text before the cursor is kept composed
+    /// (NFC), so "e" followed by U+0301 becomes "é".
+    pub fn insert(&mut self, c : char) {
+        let offset = self.byte_offset(self.cursor);
+        let mut text : String = self.text[..offset].chars().chain(Some(c)).nfc().collect();
+        self.cursor = text.graphemes(true).count();
+        text.push_str(&self.text[offset..]);
+        self.text = text;
+    }
+
+    /// Removes the grapheme at `pos`, keeping the cursor on the same text
+    pub fn remove(&mut self, pos : usize) -> Option<String> {
+        let (offset, grapheme) = self.text.grapheme_indices(true).nth(pos)?;
+        let removed = String::from(grapheme);
+        self.text.replace_range(offset..offset + removed.len(), "");
+        if pos < self.cursor {
+            self.cursor -= 1;
+        }
+
+        Some(removed)
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.len());
+    }
+
+    /// Empties the buffer, returning its text
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    /// Byte offset of the grapheme at `pos`, or the length of the text past the end
+    fn byte_offset(&self, pos : usize) -> usize {
+        self.text.grapheme_indices(true).nth(pos).map_or(self.text.len(), |(offset, _)| offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(text : &str) -> InputBuffer {
+        let mut input = InputBuffer::default();
+        text.chars().for_each(|c| input.insert(c));
+        input
+    }
+
+    #[test]
+    fn insert_cyrillic() {
+        let mut input = typed("ёлка");
+        assert_eq!((input.len(), input.cursor()), (4, 4));
+
+        input.move_left();
+        input.move_left();
+        input.insert('x');
+        assert_eq!(input.as_str(), "ёлxка");
+        assert_eq!(input.cursor(), 3);
+    }
+
+    #[test]
+    fn combining_character_joins_previous_grapheme() {
+        let input = typed("cafe\u{301}");
+        assert_eq!(input.as_str(), "caf\u{e9}");
+        assert_eq!((input.len(), input.cursor()), (4, 4));
+        assert_eq!(input, typed("caf\u{e9}"));
+
+        // in the middle of the text, the cursor stays after the accented letter
+        let mut input = typed("ab");
+        input.move_left();
+        input.insert('\u{301}');
+        assert_eq!(input.as_str(), "\u{e1}b");
+        assert_eq!(input.cursor(), 1);
+    }
+
+    #[test]
+    fn remove_whole_graphemes() {
+        // decomposed text isn't composed until it is edited
+        let mut input = InputBuffer::new(String::from("cafe\u{301}"));
+        assert_eq!(input.len(), 4);
+        assert_eq!(input.grapheme(3), Some("e\u{301}"));
+        assert_eq!(input.remove(3).as_deref(), Some("e\u{301}"));
+        assert_eq!(input.as_str(), "caf");
+        assert_eq!(input.cursor(), 3);
+        assert_eq!(input.remove(3), None);
+    }
+
+    #[test]
+    fn delete_and_backspace_at_cursor() {
+        let mut input = typed("ёлка");
+        input.move_left();
+        input.move_left();
+
+        // delete removes the grapheme after the cursor, which doesn't move
+        let pos = input.cursor();
+        assert_eq!(input.remove(pos).as_deref(), Some("к"));
+        assert_eq!((input.as_str(), input.cursor()), ("ёла", 2));
+
+        // backspace removes the one before it, moving it back
+        let pos = input.cursor() - 1;
+        assert_eq!(input.remove(pos).as_deref(), Some("л"));
+        assert_eq!((input.as_str(), input.cursor()), ("ёа", 1));
+    }
+
+    #[test]
+    fn cursor_stays_within_text() {
+        let mut input = typed("é");
+        input.move_right();
+        assert_eq!(input.cursor(), 1);
+        input.move_left();
+        input.move_left();
+        assert_eq!(input.cursor(), 0);
+
+        assert_eq!(input.take(), "é");
+        assert_eq!((input.len(), input.cursor()), (0, 0));
+    }
+
+    #[test]
+    fn split_at_cursor() {
+        let mut input = typed("ёлка");
+        assert_eq!(input.split_at_cursor(), ("ёлка", ""));
+        input.move_left();
+        input.move_left();
+        assert_eq!(input.split_at_cursor(), ("ёл", "ка"));
+        input.move_left();
+        input.move_left();
+        assert_eq!(input.split_at_cursor(), ("", "ёлка"));
+    }
+}
@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use super::config::{Config, Mode, DURATION_PRESETS, NUMBER_RATE_PRESETS, WORD_COUNT_PRESETS};
+use super::quotes::QuoteLength;
+use super::session::{Difficulty, SubmitPolicy};
+use super::text::Indent;
+
+/// Largest number that can be typed into a numeric option
+pub const MAX_CUSTOM_VALUE : u64 = 9999;
+
+/// Settings that can be changed from the menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuRow {
+    Mode,
+    Duration,
+    WordCount,
+    Language,
+    QuoteLength,
+    Indent,
+    Punctuation,
+    Numbers,
+    Submit,
+    Difficulty,
+    Backtrack,
+}
+
+impl MenuRow {
+    /// Rows shown for the given settings, top to bottom
+    pub fn rows(config : &Config) -> Vec<Self> {
+        let mut rows = vec![MenuRow::Mode];
+        match config.mode {
+            Mode::Time => rows.extend([MenuRow::Duration, MenuRow::Language, MenuRow::Punctuation, MenuRow::Numbers]),
+            Mode::Words => rows.extend([MenuRow::WordCount, MenuRow::Language, MenuRow::Punctuation, MenuRow::Numbers]),
+            Mode::Quote => rows.push(MenuRow::QuoteLength),
+            Mode::Text | Mode::Zen => {  },
+            Mode::Code => rows.push(MenuRow::Indent),
+        }
+        if config.mode != Mode::Zen {
+            rows.push(MenuRow::Submit);
+            if config.policy == SubmitPolicy::Lenient {
+                rows.push(MenuRow::Backtrack);
+            }
+            rows.push(MenuRow::Difficulty);
+        }
+
+        rows
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MenuRow::Mode => "Mode",
+            MenuRow::Duration => "Duration",
+            MenuRow::WordCount => "Words",
+            MenuRow::Language => "Language",
+            MenuRow::QuoteLength => "Length",
+            MenuRow::Indent => "Indentation",
+            MenuRow::Punctuation => "Punctuation",
+            MenuRow::Numbers => "Numbers",
+            MenuRow::Submit => "Mistakes",
+            MenuRow::Difficulty => "Difficulty",
+            MenuRow::Backtrack => "Backspace Into Words",
+        }
+    }
+
+    pub fn value(&self, config : &Config) -> String {
+        match self {
+            MenuRow::Mode => config.mode.to_string(),
+            MenuRow::Duration => numeric_value(format!("{}s", config.duration.as_secs()), &DURATION_PRESETS, config.duration.as_secs()),
+            MenuRow::WordCount => numeric_value(config.word_count.to_string(), &WORD_COUNT_PRESETS, config.word_count as u64),
+            MenuRow::Language => config.wordlist().name.clone(),
+            MenuRow::QuoteLength => config.quote_length.to_string(),
+            MenuRow::Indent => match config.indent {
+                Indent::Auto => String::from("auto"),
+                Indent::Required => String::from("required"),
+            },
+            MenuRow::Punctuation => on_off(config.modifiers.punctuation),
+            MenuRow::Numbers => match config.modifiers.number_rate {
+                0 => String::from("off"),
+                rate => numeric_value(format!("{}%", rate), &NUMBER_RATE_PRESETS, rate),
+            },
+            MenuRow::Submit => config.policy.to_string(),
+            MenuRow::Difficulty => config.difficulty.to_string(),
+            MenuRow::Backtrack => on_off(config.backtrack),
+        }
+    }
+
+    /// Selects the next (or previous) value of the option
+    pub fn cycle(&self, config : &mut Config, forward : bool) {
+        match self {
+            MenuRow::Mode => {
+                config.mode = cycle(&config.available_modes(), config.mode, forward);
+            },
+            MenuRow::Duration => {
+                let secs = cycle_numeric(&DURATION_PRESETS, config.duration.as_secs(), forward);
+                config.duration = Duration::from_secs(secs);
+            },
+            MenuRow::WordCount => {
+                config.word_count = cycle_numeric(&WORD_COUNT_PRESETS, config.word_count as u64, forward) as usize;
+            },
+            MenuRow::Language => {
+                let language = String::from(cycle(&config.languages(), config.wordlist().name.as_str(), forward));
+                config.language = language;
+            },
+            MenuRow::QuoteLength => {
+                config.quote_length = cycle(&QuoteLength::ALL, config.quote_length, forward);
+            },
+            MenuRow::Indent => {
+                config.indent = cycle(&[Indent::Auto, Indent::Required], config.indent, forward);
+            },
+            MenuRow::Punctuation => {
+                config.modifiers.punctuation = !config.modifiers.punctuation;
+            },
+            MenuRow::Numbers => {
+                config.modifiers.number_rate = cycle_numeric(&NUMBER_RATE_PRESETS, config.modifiers.number_rate, forward);
+            },
+            MenuRow::Submit => {
+                config.policy = cycle(&SubmitPolicy::ALL, config.policy, forward);
+            },
+            MenuRow::Difficulty => {
+                config.difficulty = cycle(&Difficulty::ALL, config.difficulty, forward);
+            },
+            MenuRow::Backtrack => {
+                config.backtrack = !config.backtrack;
+            },
+        }
+    }
+
+    /// Sets a typed-in value, returning false if the option isn't numeric
+    pub fn set_custom(&self, config : &mut Config, value : u64) -> bool {
+        match self {
+            MenuRow::Duration => config.duration = Duration::from_secs(value),
+            MenuRow::WordCount => config.word_count = value as usize,
+            MenuRow::Numbers => config.modifiers.number_rate = value.min(100),
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+fn on_off(value : bool) -> String {
+    String::from(if value { "on" } else { "off" })
+}
+
+fn numeric_value(mut value : String, presets : &[u64], current : u64) -> String {
+    if !presets.contains(&current) {
+        value.push_str(" (custom)");
+    }
+
+    value
+}
+
+fn cycle<T : PartialEq + Copy>(options : &[T], current : T, forward : bool) -> T {
+    let index = options.iter().position(|&option| option == current).unwrap_or(0);
+    let next = if forward {
+        (index + 1) % options.len()
+    } else {
+        (index + options.len() - 1) % options.len()
+    };
+
+    options[next]
+}
+
+/// Cycles through the presets, going through the current value as well if it is a custom one
+fn cycle_numeric(presets : &[u64], current : u64, forward : bool) -> u64 {
+    let mut options = presets.to_vec();
+    if !presets.contains(&current) {
+        options.push(current);
+    }
+
+    cycle(&options, current, forward)
+}
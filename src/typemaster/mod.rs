@@ -1,10 +1,35 @@
-mod wordlist;
-use wordlist::get_wordlist;
+pub mod wordlist;
+
+pub mod quotes;
+use quotes::{Quote, random_quote};
+
+pub mod text;
+use text::{Separator, Word, parse_code, parse_paragraphs};
+
+pub mod modifiers;
+
+pub mod highlight;
+use highlight::{Language, TokenKind, highlight};
+
+pub mod clock;
+pub mod input;
+pub mod session;
+pub mod stats;
+use stats::TestResult;
+
+pub mod config;
+use config::{Config, Mode};
+
+mod menu;
+use menu::{MenuRow, MAX_CUSTOM_VALUE};
+use session::{TypingSession, Input, Limit, SessionState};
 
 use tui::{
+    Frame,
     Terminal,
     backend::Backend,
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Axis, Block, Borders, Chart, Clear, Dataset, GraphType, Paragraph, Wrap},
+    symbols::Marker,
     layout::{Layout, Alignment, Rect, Constraint, Direction},
     text::{Span, Spans},
     style::{Style, Color, Modifier}
@@ -14,122 +39,187 @@ use crossterm::{
     event::{self, Event, KeyCode, KeyModifiers}
 };
 
-use rand::{
-    thread_rng,
-    seq::SliceRandom
-};
+use rand::thread_rng;
+
+use unicode_segmentation::UnicodeSegmentation;
 
-use std::sync::Mutex;
-use std::thread;
 use std::time::Duration;
 
-const COUNTDOWN_START : usize = 60; // initial countdown value (in seconds)
-static COUNTDOWN : Mutex<usize> = Mutex::new(0);
-static IS_PLAYING : Mutex<bool> = Mutex::new(false);
-static SHOW_RESULT : Mutex<bool> = Mutex::new(false);
+const HISTORY_LEN : usize = 5; // number of past results listed in the menu
+const WORD_BUFFER : usize = 50; // generated words kept ahead of the current one
+const VISIBLE_LINES : usize = 3; // lines of the words box, the current one being the first or second
+
+/// Parts of the screen that changed since they were last drawn
+#[derive(Debug, Clone, Copy, Default)]
+struct Dirty {
+    layout : bool, // menu, results popup or terminal size, everything has to be laid out
+    words : bool, // words box and input line, laid out again
+    stats : bool, // clock and live metrics, drawn along with the cached words box
+}
+
+impl Dirty {
+    fn all() -> Self {
+        Self { layout: true, words: true, stats: true }
+    }
+
+    fn any(&self) -> bool {
+        self.layout || self.words || self.stats
+    }
+}
 
 pub struct TypeMaster {
-    wordlist : Vec<&'static str>,
+    session : TypingSession,
+    wordlist : Vec<Word>, // words of the current test, kept for retries
+    quote : Option<&'static Quote>, // quote of the current test, in quote mode
+    result : Option<TestResult>, // set while the results popup is shown
+    history : Vec<TestResult>, // finished tests, oldest first
+    config : Config,
+    menu_row : usize, // selected row of the menu
+    custom_value : u64, // number being typed into the selected menu row
+    view_start : usize, // index of the first word shown in the words box
+    words_view : (Vec<Spans<'static>>, u16), // laid out content of the words box and its scroll
+    shown_second : u64, // elapsed second shown on screen
+    dirty : Dirty,
     show_play : bool,
-    word_input : String,
-    cursor_pos : usize,
-    char_count : usize,
+}
+
+impl Default for TypeMaster {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TypeMaster {
     pub fn new() -> Self {
-        Self { wordlist: vec![], show_play: false, word_input: String::new(), cursor_pos: 0, char_count : 0 }
+        Self::with_config(Config::default())
+    }
+
+    pub fn with_config(config : Config) -> Self {
+        Self {
+            session: TypingSession::new(vec![], config.limit()),
+            wordlist: vec![],
+            quote: None,
+            result: None,
+            history: vec![],
+            config,
+            menu_row: 0,
+            custom_value: 0,
+            view_start: 0,
+            words_view: (vec![], 0),
+            shown_second: 0,
+            dirty: Dirty::all(),
+            show_play: false,
+        }
     }
 
     pub fn run<B: Backend>(&mut self, terminal : &mut Terminal<B>) -> Result<(), std::io::Error>{
         loop {
-            self.draw(terminal)?;
-            
-            // this ensures that the terminal doesn't only update on events
-            if !event::poll(Duration::from_millis(100))? {
-                continue
+            self.session.tick();
+            if self.session.state() == SessionState::Finished && self.result.is_none() {
+                let mut result = self.session.result();
+                result.mode = self.config.describe();
+                result.source = self.quote.map(|quote| String::from(quote.source));
+                self.history.push(result.clone());
+                self.result = Some(result);
+                self.dirty.layout = true;
+            }
+
+            // the clock and the live metrics are refreshed once per second
+            let elapsed = self.session.elapsed();
+            if elapsed.as_secs() != self.shown_second {
+                self.shown_second = elapsed.as_secs();
+                self.dirty.stats = true;
+            }
+
+            if self.dirty.any() {
+                self.draw(terminal)?;
+            }
+
+            // while a test runs, wake up when the next second starts, which is
+            // also the deadline of timed tests, and otherwise wait for an event
+            if self.session.state() == SessionState::Running {
+                let next_second = Duration::from_secs(elapsed.as_secs() + 1);
+                if !event::poll(next_second.saturating_sub(self.session.elapsed()))? {
+                    continue
+                }
             }
 
-            if let Event::Key(key) = event::read()? {
+            let key = match event::read()? {
+                Event::Key(key) => key,
+                Event::Resize(_, _) => {
+                    self.dirty = Dirty::all();
+                    continue;
+                },
+                _ => continue,
+            };
+
+            if self.result.is_some() {
+                match key.code {
+                    KeyCode::Char('r') | KeyCode::Char('R') => self.retry(),
+                    KeyCode::Enter | KeyCode::Char('n') | KeyCode::Char('N') => self.play(),
+                    KeyCode::Esc | KeyCode::Char('m') | KeyCode::Char('M') => self.menu(),
+                    _ => continue,
+                }
+                self.dirty.layout = true;
+                continue;
+            }
+
+            if !self.show_play {
                 match key.code {
                     KeyCode::Esc => break,
-                    KeyCode::Enter => {
-                        if *SHOW_RESULT.lock().unwrap() {
-                            *SHOW_RESULT.lock().unwrap() = false;
-                        } else {
-                            self.play();
-                        }
-                    },
-                    KeyCode::Backspace => {
-                        if self.cursor_pos > 0 {
-                            self.word_input.remove(self.cursor_pos - 1);
-                            self.cursor_pos -= 1;
-                        }
-                    },
-                    KeyCode::Delete => {
-                        if self.word_input.len() > self.cursor_pos {
-                            self.word_input.remove(self.cursor_pos);
-                        }
-                    },
-                    KeyCode::Left => {
-                        if self.cursor_pos > 0 {
-                            self.cursor_pos -= 1;
-                        }
-                    },
-                    KeyCode::Right => {
-                        if self.cursor_pos < self.word_input.len() {
-                            self.cursor_pos += 1;
-                        }
-                    },
-                    KeyCode::Char(' ') => {
-                        if self.wordlist.len() > 0 && self.word_input == self.wordlist[0] {
-                            self.char_count += self.word_input.len();
-                            self.wordlist.remove(0);
-                            self.word_input.clear();
-                            self.cursor_pos = 0;
-                        }
-                    },
-                    KeyCode::Char(c) => {
-                        if (key.modifiers.bits() & KeyModifiers::CONTROL.bits()) > 0 {
-                            if c == 'u' || c == 'U' {
-                                self.word_input.clear();
-                                self.cursor_pos = 0;
-                            } else if c == 'c' || c == 'C' {
-                                *COUNTDOWN.lock().unwrap() = 0;
-                                // wait for thread to exit
-                                while *IS_PLAYING.lock().unwrap() {
-                                    
-                                }
-                                *SHOW_RESULT.lock().unwrap() = false;
-                                self.char_count = 0;
-                                self.cursor_pos = 0;
-                                self.word_input.clear();
-                            }
-                        } else {
-                            if !*IS_PLAYING.lock().unwrap() {
-                                thread::spawn(|| {
-                                    while *COUNTDOWN.lock().unwrap() > 0 {
-                                        thread::sleep(Duration::from_secs(1));
-                                        if *COUNTDOWN.lock().unwrap() > 0 {
-                                            *COUNTDOWN.lock().unwrap() -= 1;
-                                        }
-                                    }
-
-                                    *IS_PLAYING.lock().unwrap() = false;
-                                    *SHOW_RESULT.lock().unwrap() = true;
-                                });
-
-                                *IS_PLAYING.lock().unwrap() = true;
-                            }
-
-                            if *COUNTDOWN.lock().unwrap() > 0 {
-                                self.word_input.insert(self.cursor_pos, c);
-                                self.cursor_pos += 1;
-                            }
-                        }
-                    },
-                    _ => {  }
+                    KeyCode::Enter => self.play(),
+                    KeyCode::Up => self.select_menu_row(false),
+                    KeyCode::Down => self.select_menu_row(true),
+                    KeyCode::Left => self.cycle_menu_option(false),
+                    KeyCode::Right => self.cycle_menu_option(true),
+                    KeyCode::Backspace => self.edit_custom_value(None),
+                    KeyCode::Char(c) if c.is_ascii_digit() => self.edit_custom_value(c.to_digit(10)),
+                    _ => continue,
                 }
+                self.dirty.layout = true;
+                continue;
+            }
+
+            // only the parts of the screen showing what the key changed are redrawn
+            let input = (String::from(self.session.word_input()), self.session.cursor_pos(), self.session.submitted(), self.session.state());
+            let char_stats = self.session.char_stats();
+            match key.code {
+                KeyCode::Esc => break,
+                KeyCode::Enter => {
+                    if self.session.state() == SessionState::Running && self.session.takes_enter() {
+                        self.session.handle(Input::Enter);
+                    } else {
+                        self.play();
+                    }
+                },
+                KeyCode::Tab => self.session.handle(Input::Char('\t')),
+                KeyCode::Backspace => self.session.handle(Input::Backspace),
+                KeyCode::Delete => self.session.handle(Input::Delete),
+                KeyCode::Left => self.session.handle(Input::Left),
+                KeyCode::Right => self.session.handle(Input::Right),
+                KeyCode::Char(' ') => self.session.handle(Input::Char(' ')),
+                KeyCode::Char(c) => {
+                    if (key.modifiers.bits() & KeyModifiers::CONTROL.bits()) > 0 {
+                        if c == 'u' || c == 'U' {
+                            self.session.handle(Input::ClearWord);
+                        } else if c == 'c' || c == 'C' {
+                            self.session.stop();
+                        } else if (c == 'd' || c == 'D') && self.session.limit() == Limit::Zen && self.session.state() == SessionState::Running {
+                            self.session.finish();
+                        }
+                    } else {
+                        self.session.handle(Input::Char(c));
+                    }
+                },
+                _ => {  }
+            }
+            self.generate_words();
+            if input != (String::from(self.session.word_input()), self.session.cursor_pos(), self.session.submitted(), self.session.state()) {
+                self.dirty.words = true;
+                self.dirty.stats = true;
+            }
+            if char_stats != self.session.char_stats() {
+                self.dirty.stats = true;
             }
         }
 
@@ -141,16 +231,154 @@ impl TypeMaster {
             self.show_play = true;
         }
 
-        if !*IS_PLAYING.lock().unwrap() {
-            self.wordlist = get_wordlist();
-            self.wordlist.shuffle(&mut thread_rng());
-            self.char_count = 0;
-            self.cursor_pos = 0;
-            self.word_input.clear();
-            *COUNTDOWN.lock().unwrap() = COUNTDOWN_START;
+        if self.session.state() != SessionState::Running {
+            self.quote = None;
+            self.wordlist = match (self.config.mode, &self.config.text, &self.config.code) {
+                (Mode::Quote, _, _) => {
+                    let quote = random_quote(self.config.quote_length);
+                    self.quote = Some(quote);
+                    quote.text.split_whitespace().map(Word::new).collect()
+                },
+                (Mode::Text, Some(text), _) => parse_paragraphs(text),
+                (Mode::Code, _, Some(code)) => parse_code(&code.contents, self.config.indent),
+                // generated words are added as the test goes
+                _ => vec![],
+            };
+            self.retry();
+        }
+    }
+
+    /// Restarts the test with the same words
+    fn retry(&mut self) {
+        self.result = None;
+        self.session = TypingSession::new(self.wordlist.clone(), self.config.limit())
+            .with_policy(self.config.policy)
+            .with_difficulty(self.config.difficulty)
+            .with_backtrack(self.config.backtrack);
+        self.view_start = 0;
+        self.generate_words();
+        // the words changed even if the input looks the same
+        self.dirty.layout = true;
+    }
+
+    /// Keeps a buffer of random words ahead of the current one in time and words modes
+    fn generate_words(&mut self) {
+        if !matches!(self.config.mode, Mode::Time | Mode::Words) || self.session.upcoming().len() >= WORD_BUFFER {
+            return;
+        }
+
+        let mut count = WORD_BUFFER;
+        let mut last = false; // whether this batch ends the test
+        if let Limit::Words(total) = self.config.limit() {
+            let left = total.saturating_sub(self.session.wordlist().len());
+            last = left <= count;
+            count = count.min(left);
+        }
+        if count == 0 {
+            return;
+        }
+
+        let mut rng = thread_rng();
+        let words = self.config.wordlist().random_words(count, &mut rng);
+        let previous = self.session.wordlist().last().map(|word| word.text.as_str());
+        let words : Vec<Word> = self.config.modifiers.apply(&words, previous, last, &mut rng).iter().map(|word| Word::new(word)).collect();
+        // keep them for retries
+        self.wordlist.extend(words.iter().cloned());
+        self.session.extend(words);
+    }
+
+    fn menu(&mut self) {
+        self.result = None;
+        self.session = TypingSession::new(vec![], self.config.limit());
+        self.show_play = false;
+    }
+
+    fn select_menu_row(&mut self, forward : bool) {
+        let rows = MenuRow::rows(&self.config).len();
+        self.menu_row = if forward {
+            (self.menu_row + 1) % rows
+        } else {
+            (self.menu_row + rows - 1) % rows
+        };
+        self.custom_value = 0;
+    }
+
+    fn selected_menu_row(&self) -> MenuRow {
+        let rows = MenuRow::rows(&self.config);
+        rows[self.menu_row.min(rows.len() - 1)]
+    }
+
+    fn cycle_menu_option(&mut self, forward : bool) {
+        self.selected_menu_row().cycle(&mut self.config, forward);
+        self.custom_value = 0;
+    }
+
+    /// Appends a digit to the value of the selected numeric option, or removes the last one
+    fn edit_custom_value(&mut self, digit : Option<u32>) {
+        self.custom_value = match digit {
+            Some(digit) => (self.custom_value * 10 + u64::from(digit)).min(MAX_CUSTOM_VALUE),
+            None => self.custom_value / 10,
+        };
+
+        if self.custom_value > 0 && !self.selected_menu_row().set_custom(&mut self.config, self.custom_value) {
+            self.custom_value = 0;
+        }
+    }
+
+    /// Content of the words box and the number of rows to scroll it by
+    fn words_lines(&mut self, area : Rect, done : Color) -> (Vec<Spans<'static>>, u16) {
+        let style = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+        let height = usize::from(area.height);
+
+        match &self.config.code {
+            Some(code) if self.config.mode == Mode::Code => {
+                let upcoming = self.session.upcoming();
+                let shown = &upcoming[..upcoming.len().min(height)];
+                (code_lines(shown, Language::detect(&code.path), self.session.word_input(), style, done), 0)
+            },
+            _ if self.config.mode == Mode::Zen => {
+                // only the end of the text can be in view, every word taking at least two cells
+                let typed = self.session.typed();
+                let mut shown = typed[typed.len().saturating_sub(height * usize::from(area.width) / 2)..].to_vec();
+                shown.push(Word::new(self.session.word_input()));
+                let lines = word_lines(&shown, style);
+                // zen text grows as it is typed, keep its end in view
+                let scroll = wrapped_height(&lines, area.width).saturating_sub(area.height);
+                (lines, scroll)
+            },
+            _ => {
+                let current = self.session.typed().len();
+                // going back into a previous word may leave the view
+                self.view_start = self.view_start.min(current);
+                loop {
+                    let (lines, line_starts) = progress_lines(self.session.wordlist(), self.session.typed(), self.session.word_input(), self.view_start, area.width, style, done);
+                    let current_line = line_starts.iter().rposition(|&start| start <= current).unwrap_or(0);
+                    // scroll once the current word reaches the last line, keeping the previous line in view
+                    if current_line + 1 < VISIBLE_LINES {
+                        return (lines, 0);
+                    }
+                    self.view_start = line_starts[current_line - 1];
+                }
+            },
         }
     }
 
+    /// Test settings shown above the play button
+    fn menu_lines(&self) -> Vec<Spans<'static>> {
+        let label_style = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+        let value_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        let selected = self.selected_menu_row();
+
+        MenuRow::rows(&self.config).into_iter().map(|row| {
+            let value = if row == selected {
+                format!("< {} >", row.value(&self.config))
+            } else {
+                row.value(&self.config)
+            };
+            Spans::from(vec![Span::styled(format!("{}: ", row.label()), label_style), Span::styled(value, value_style)])
+        }).collect()
+    }
+
     fn draw<B: Backend>(&mut self, terminal : &mut Terminal<B>) -> Result<(), std::io::Error>{
 		// colors
 		let blue = Color::Rgb(0x20, 0x45, 0x90);
@@ -171,11 +399,18 @@ impl TypeMaster {
 			.border_style(Style::default().fg(baby_blue).add_modifier(Modifier::BOLD))
 			.style(Style::default().bg(blue));
 
-        let comment = Paragraph::new(Span::styled("Made by rdbo | Start Typing to Begin Test | ESC: Exit | ENTER: Restart | Ctrl-C: Stop Test | Ctrl-U: Clear Line | Left/Right Arrow: Move Cursor | Backspace: Remove Previous Character | Delete: Remove Current Character", Style::default().fg(Color::White))).alignment(Alignment::Center).wrap(Wrap { trim: true});
+        let comment = Paragraph::new(Span::styled("Made by rdbo | Start Typing to Begin Test | ESC: Exit | ENTER: Restart / New Line (Text, Code, Zen) | Up/Down Arrow (Menu): Select Option | Left/Right Arrow (Menu): Change Option | 0-9 (Menu): Custom Value | Ctrl-C: Stop Test | Ctrl-D: Finish Zen Test | Ctrl-U: Clear Line | Left/Right Arrow: Move Cursor | Backspace: Remove Previous Character | Delete: Remove Current Character", Style::default().fg(Color::White))).alignment(Alignment::Center).wrap(Wrap { trim: true});
 
         let play_text_block = Block::default().style(Style::default().bg(Color::White)).borders(Borders::ALL).border_style(Style::default().fg(baby_blue).add_modifier(Modifier::BOLD));
         let play_text = Paragraph::new(Span::styled("PRESS ENTER TO PLAY", Style::default().fg(baby_blue).add_modifier(Modifier::BOLD))).alignment(Alignment::Center).wrap(Wrap{ trim: true });
 
+        // laid out ahead of drawing, as it moves the view of the words box
+        if self.show_play && (self.dirty.words || self.dirty.layout) {
+            self.words_view = self.words_lines(centered_rect(40, 40, terminal.size()?), blue);
+        }
+        self.dirty = Dirty::default();
+        let (words_lines, scroll) = self.words_view.clone();
+
 		// draw
         terminal.draw(|f| {
             let size = f.size();
@@ -188,62 +423,64 @@ impl TypeMaster {
             if !self.show_play {
     			f.render_widget(play_text_block, center_area);
                 f.render_widget(play_text, play_text_area);
+
+                let menu_lines = self.menu_lines();
+                let menu_height = menu_lines.len() as u16;
+                let menu_area = Rect::new(center_area.x, center_area.y.saturating_sub(menu_height + 1), center_area.width, menu_height).intersection(size);
+                f.render_widget(Paragraph::new(menu_lines).alignment(Alignment::Center), menu_area);
+
+                if !self.history.is_empty() {
+                    let history_area = Rect::new(size.x + 1, center_area.y + center_area.height + 1, size.width - 2, HISTORY_LEN as u16 + 1).intersection(size);
+                    f.render_widget(Paragraph::new(history_lines(&self.history)).alignment(Alignment::Center), history_area);
+                }
             } else {
                 let words_box_area = centered_rect(40, 40, size);
                 let words_block_area = Rect::new(words_box_area.x - 2, words_box_area.y - 2, words_box_area.width + 4, words_box_area.height + 4);
                 let words_block = Block::default().style(Style::default().bg(baby_blue)).borders(Borders::ALL);
-                let words = self.wordlist.join(" ");
-                let words_box = Paragraph::new(Span::styled(words, Style::default().fg(Color::White).add_modifier(Modifier::BOLD))).wrap(Wrap{ trim: true });
+                // keep leading whitespace so that code stays indented
+                let trim = self.config.mode != Mode::Code;
+                let words_box = Paragraph::new(words_lines).wrap(Wrap{ trim }).scroll((scroll, 0));
 
                 let input_area = Rect::new(words_block_area.x, words_block_area.height + words_block_area.y + 2, words_block_area.width, 2);
                 let input_style = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
                 let cursor_style = input_style.bg(baby_blue).fg(Color::Yellow);
                 let mut input_content : Vec<Span> = vec![Span::styled(String::from("> "), input_style)];
                 // paint cursor
-                if self.cursor_pos < self.word_input.len() {
-                    input_content.push(Span::styled(&self.word_input[0..self.cursor_pos], input_style));
-                    input_content.push(Span::styled(&self.word_input[self.cursor_pos..(self.cursor_pos + 1)], cursor_style));
-                    input_content.push(Span::styled(&self.word_input[(self.cursor_pos + 1)..], input_style));
-                } else {
-                    input_content.push(Span::styled(&self.word_input, input_style));
-                    input_content.push(Span::styled("|", cursor_style.fg(baby_blue)));
+                let (before_cursor, after_cursor) = self.session.input().split_at_cursor();
+                input_content.push(Span::styled(show_tabs(before_cursor), input_style));
+                match after_cursor.graphemes(true).next() {
+                    Some(at_cursor) => {
+                        input_content.push(Span::styled(show_tabs(at_cursor), cursor_style));
+                        input_content.push(Span::styled(show_tabs(&after_cursor[at_cursor.len()..]), input_style));
+                    },
+                    None => input_content.push(Span::styled("|", cursor_style.fg(baby_blue))),
                 }
                 let input_text = Paragraph::new(Spans::from(input_content)).wrap(Wrap{ trim: true });
 
-                let word_count = self.char_count / 5;
                 let word_count_area = Rect::new(input_area.x, input_area.y + 2, input_area.width, 1);
-                let mut word_count_content = String::from("Words: ");
-                word_count_content.push_str(&(word_count).to_string());
+                let word_count_content = format!("Words: {} | Accuracy: {:.1}% | Raw WPM: {:.0} | Gross WPM: {:.0}", self.session.word_count(), self.session.accuracy(), self.session.raw_wpm(), self.session.gross_wpm());
                 let word_count_text = Paragraph::new(Span::styled(word_count_content, Style::default().add_modifier(Modifier::BOLD)));
 
-                let mut countdown_secs = *COUNTDOWN.lock().unwrap();
-                let countdown_mins = countdown_secs / 60;
-                countdown_secs -= countdown_mins * 60;
-                let mut countdown_mins_str = countdown_mins.to_string();
-                let mut countdown_secs_str = countdown_secs.to_string();
-
-                for s in [&mut countdown_mins_str, &mut countdown_secs_str] {
-                    if s.len() < 2 {
-                        s.insert(0, '0');
-                    }
-                }
-
-                let mut countdown_content = String::new();
-                countdown_content.push_str(&countdown_mins_str);
-                countdown_content.push_str(":");
-                countdown_content.push_str(&countdown_secs_str);
+                let countdown_content = match (self.session.limit(), self.session.remaining()) {
+                    (Limit::Words(count), _) => format!("{}/{}", self.session.submitted(), count),
+                    (_, Some(remaining)) => {
+                        // round up so the clock shows the full duration until the first second passes
+                        let mut countdown_secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+                        let countdown_mins = countdown_secs / 60;
+                        countdown_secs -= countdown_mins * 60;
+                        format!("{:02}:{:02}", countdown_mins, countdown_secs)
+                    },
+                    (Limit::Zen, _) => {
+                        let elapsed_secs = self.session.elapsed().as_secs();
+                        format!("{:02}:{:02}", elapsed_secs / 60, elapsed_secs % 60)
+                    },
+                    _ => String::new(),
+                };
                 let countdown_text = Paragraph::new(Span::styled(countdown_content, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))).alignment(Alignment::Right);
                 let countdown_area = Rect::new(words_block_area.x, words_block_area.y - 2, words_block_area.width, 2);
 
-                let ellapsed_time = COUNTDOWN_START - *COUNTDOWN.lock().unwrap();
-                let wpm = if ellapsed_time > 0 {
-                    word_count * (60 / ellapsed_time)
-                } else {
-                    0
-                };
                 let wpm_area = countdown_area;
-                let mut wpm_content = String::from("WPM: ");
-                wpm_content.push_str(&(wpm).to_string());
+                let wpm_content = format!("WPM: {:.0}", self.session.net_wpm());
                 let wpm_text = Paragraph::new(Span::styled(wpm_content, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))).alignment(Alignment::Center);
 
                 f.render_widget(countdown_text, countdown_area);
@@ -253,19 +490,8 @@ impl TypeMaster {
                 f.render_widget(word_count_text, word_count_area);
                 f.render_widget(wpm_text, wpm_area);
 
-                // TODO: Add popup with result message
-                if *SHOW_RESULT.lock().unwrap() {
-                    let len = if self.word_input.len() <= self.wordlist[0].len() { self.word_input.len() } else { self.wordlist[0].len() };
-                    for i in 0..len {
-                        if self.word_input.chars().nth(i).unwrap() != self.wordlist[0].chars().nth(i).unwrap() {
-                            break;
-                        }
-
-                        self.char_count += 1;
-                    }
-                    self.cursor_pos = 0;
-                    self.word_input.clear();
-                    *SHOW_RESULT.lock().unwrap() = false; // TODO: Remove this. SHOW_RESULT
+                if let Some(result) = &self.result {
+                    draw_result(f, centered_rect(70, 80, size), result, blue, baby_blue);
                 }
             }
         })?;
@@ -274,6 +500,269 @@ impl TypeMaster {
     }
 }
 
+/// Makes tabs visible, keeping their width
+fn show_tabs(text : &str) -> String {
+    text.replace('\t', "→   ")
+}
+
+/// Lays out the words as paragraphs, with a blank line at every paragraph break
+fn word_lines(words : &[Word], style : Style) -> Vec<Spans<'static>> {
+    let mut lines = vec![];
+    let mut line = String::new();
+
+    for word in words {
+        line.push_str(&show_tabs(&word.indent));
+        line.push_str(&show_tabs(&word.text));
+        match word.separator {
+            Separator::Space => line.push(' '),
+            Separator::Newline => {
+                lines.push(Spans::from(Span::styled(std::mem::take(&mut line), style)));
+                lines.push(Spans::default());
+            },
+            Separator::Line => lines.push(Spans::from(Span::styled(std::mem::take(&mut line), style))),
+        }
+    }
+    lines.push(Spans::from(Span::styled(line, style)));
+
+    lines
+}
+
+/// Lays out the target words from the one at `start`, wrapped to `width`,
+/// coloring the typed characters by correctness and underlining the current word
+///
+/// Only the lines up to the current one and the ones following it up to
+/// [`VISIBLE_LINES`] are laid out. Returns them along with the index of the
+/// first word of each line.
+fn progress_lines(words : &[Word], typed : &[Word], input : &str, start : usize, width : u16, style : Style, done : Color) -> (Vec<Spans<'static>>, Vec<usize>) {
+    let correct_style = style.fg(done);
+    let incorrect_style = style.fg(Color::White).bg(Color::Red);
+    let missed_style = style.fg(Color::Red);
+    let width = usize::from(width.max(1));
+
+    let mut lines = vec![];
+    let mut line_starts = vec![start];
+    let mut line : Vec<(&str, Style)> = vec![];
+
+    for (i, word) in words.iter().enumerate().skip(start) {
+        let current = i == typed.len();
+        let typed_text = if i < typed.len() {
+            Some(typed[i].text.as_str())
+        } else if current {
+            Some(input)
+        } else {
+            None
+        };
+
+        let mut chars : Vec<(&str, Style)> = word.indent.graphemes(true).map(|g| (g, style)).collect();
+        match typed_text {
+            Some(text) => {
+                let typed_chars : Vec<&str> = text.graphemes(true).collect();
+                let mut target_len = 0;
+                for (pos, g) in word.text.graphemes(true).enumerate() {
+                    let char_style = match typed_chars.get(pos) {
+                        Some(&t) if t == g => correct_style,
+                        Some(_) => incorrect_style,
+                        None if current => style,
+                        None => missed_style, // submitted before being finished
+                    };
+                    chars.push((g, char_style));
+                    target_len += 1;
+                }
+                // extra characters typed past the end of the word
+                chars.extend(typed_chars.iter().skip(target_len).map(|&g| (g, incorrect_style)));
+            },
+            None => chars.extend(word.text.graphemes(true).map(|g| (g, style))),
+        }
+        if current {
+            for (_, char_style) in chars.iter_mut() {
+                *char_style = char_style.add_modifier(Modifier::UNDERLINED);
+            }
+        }
+
+        if !line.is_empty() {
+            if text_width(&line) + 1 + text_width(&chars) > width {
+                lines.push(Spans::from(group_spans(&std::mem::take(&mut line))));
+                if lines.len() >= VISIBLE_LINES && i > typed.len() {
+                    return (lines, line_starts);
+                }
+                line_starts.push(i);
+            } else {
+                line.push((" ", style));
+            }
+        }
+        line.extend(chars);
+
+        if word.separator != Separator::Space {
+            lines.push(Spans::from(group_spans(&std::mem::take(&mut line))));
+            if word.separator == Separator::Newline {
+                lines.push(Spans::default());
+                line_starts.push(i + 1);
+            }
+            if lines.len() >= VISIBLE_LINES && i >= typed.len() {
+                return (lines, line_starts);
+            }
+            line_starts.push(i + 1);
+        }
+    }
+
+    if line.is_empty() && !lines.is_empty() {
+        line_starts.pop();
+    } else {
+        lines.push(Spans::from(group_spans(&line)));
+    }
+
+    (lines, line_starts)
+}
+
+/// Width of styled graphemes once tabs are made visible
+fn text_width(chars : &[(&str, Style)]) -> usize {
+    chars.iter().map(|&(g, _)| if g == "\t" { 4 } else { 1 }).sum()
+}
+
+/// Number of rows the lines take once wrapped to `width` (estimated from their length)
+fn wrapped_height(lines : &[Spans], width : u16) -> u16 {
+    let width = usize::from(width.max(1));
+    lines.iter().map(|line| line.width().max(1).div_ceil(width) as u16).sum()
+}
+
+/// Lays out lines of code, highlighting the syntax of what is left to type and
+/// the correctness of what was typed on the current line
+fn code_lines(words : &[Word], language : Option<Language>, input : &str, style : Style, done : Color) -> Vec<Spans<'static>> {
+    let correct_style = style.fg(done);
+    let incorrect_style = style.fg(Color::White).bg(Color::Red);
+    let typed : Vec<&str> = input.graphemes(true).collect();
+
+    words.iter().enumerate().map(|(line, word)| {
+        let kinds = match language {
+            Some(language) => highlight(&word.text, language),
+            None => vec![TokenKind::Plain; word.text.chars().count()],
+        };
+        let mut chars : Vec<(&str, Style)> = word.indent.graphemes(true).map(|g| (g, style)).collect();
+
+        // a grapheme takes the kind of its first character
+        let mut char_index = 0;
+        for (i, g) in word.text.graphemes(true).enumerate() {
+            let kind = kinds[char_index];
+            char_index += g.chars().count();
+            let char_style = match typed.get(i) {
+                Some(&t) if line == 0 && t == g => correct_style,
+                Some(_) if line == 0 => incorrect_style,
+                _ => match kind {
+                    TokenKind::Plain => style,
+                    TokenKind::Keyword => style.fg(Color::Yellow),
+                    TokenKind::String => style.fg(Color::LightGreen),
+                    TokenKind::Comment => style.fg(Color::Gray).remove_modifier(Modifier::BOLD),
+                    TokenKind::Number => style.fg(Color::LightMagenta),
+                },
+            };
+            chars.push((g, char_style));
+        }
+
+        Spans::from(group_spans(&chars))
+    }).collect()
+}
+
+/// Merges runs of graphemes sharing a style into spans
+fn group_spans(chars : &[(&str, Style)]) -> Vec<Span<'static>> {
+    let mut spans : Vec<Span> = vec![];
+    let mut run = String::new();
+
+    for (i, &(g, style)) in chars.iter().enumerate() {
+        run.push_str(g);
+        if chars.get(i + 1).map(|&(_, next)| next) != Some(style) {
+            spans.push(Span::styled(show_tabs(&std::mem::take(&mut run)), style));
+        }
+    }
+
+    spans
+}
+
+fn draw_result<B: Backend>(f : &mut Frame<B>, area : Rect, result : &TestResult, bg : Color, border : Color) {
+    let block = Block::default()
+        .title(Span::styled(" RESULTS ", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(bg));
+    let lines = result_lines(result);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(lines.len() as u16), Constraint::Min(0)].as_ref())
+        .split(block.inner(area));
+    let text = Paragraph::new(lines).alignment(Alignment::Center).wrap(Wrap { trim: true });
+
+    // timeline of the test, one point per sampled second
+    let point = |time : std::time::Duration, value : f64| (time.as_secs_f64(), value);
+    let raw_data : Vec<(f64, f64)> = result.samples.iter().map(|s| point(s.time, s.raw_wpm)).collect();
+    let net_data : Vec<(f64, f64)> = result.samples.iter().map(|s| point(s.time, s.net_wpm)).collect();
+    // seconds with mistakes are marked on the raw speed line, where they stand out
+    let error_data : Vec<(f64, f64)> = result.samples.iter().filter(|s| s.errors > 0).map(|s| point(s.time, s.raw_wpm)).collect();
+    let max_time = result.elapsed.as_secs_f64().max(1.0);
+    let max_wpm = raw_data.iter().chain(net_data.iter()).map(|(_, wpm)| *wpm).fold(10.0, f64::max);
+    let max_wpm = (max_wpm / 10.0).ceil() * 10.0;
+
+    let datasets = vec![
+        Dataset::default().name("raw").marker(Marker::Braille).graph_type(GraphType::Line).style(Style::default().fg(Color::Gray)).data(&raw_data),
+        Dataset::default().name("wpm").marker(Marker::Braille).graph_type(GraphType::Line).style(Style::default().fg(Color::Yellow)).data(&net_data),
+        Dataset::default().name("errors").marker(Marker::Dot).graph_type(GraphType::Scatter).style(Style::default().fg(Color::Red)).data(&error_data),
+    ];
+    let axis_style = Style::default().fg(Color::White);
+    let chart = Chart::new(datasets)
+        .style(Style::default().bg(bg))
+        .x_axis(Axis::default()
+            .title(Span::styled("seconds", axis_style))
+            .style(axis_style)
+            .bounds([0.0, max_time])
+            .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}", max_time))]))
+        .y_axis(Axis::default()
+            .title(Span::styled("wpm", axis_style))
+            .style(axis_style)
+            .bounds([0.0, max_wpm])
+            .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}", max_wpm / 2.0)), Span::raw(format!("{:.0}", max_wpm))]));
+
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+    f.render_widget(text, chunks[0]);
+    if !raw_data.is_empty() {
+        f.render_widget(chart, chunks[1]);
+    }
+}
+
+fn result_lines(result : &TestResult) -> Vec<Spans<'static>> {
+    let label_style = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+    let value_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+    let line = |label : &'static str, value : String| Spans::from(vec![Span::styled(label, label_style), Span::styled(value, value_style)]);
+    let chars = result.char_stats;
+
+    vec![
+        Spans::default(),
+        line("WPM: ", format!("{:.2}", result.net_wpm)),
+        line("Raw WPM: ", format!("{:.2}", result.raw_wpm)),
+        line("Gross WPM: ", format!("{:.2}", result.gross_wpm)),
+        line("Accuracy: ", format!("{:.2}%", result.accuracy)),
+        line("Consistency: ", format!("{:.2}%", result.consistency)),
+        line("Characters: ", format!("{} correct / {} incorrect / {} extra / {} missed", chars.correct, chars.incorrect, chars.extra, chars.missed)),
+        line("Corrected: ", chars.corrected.to_string()),
+        line("Uncorrected Errors: ", result.errors.to_string()),
+        line("Duration: ", format!("{:.1}s", result.elapsed.as_secs_f64())),
+        line("Mode: ", result.mode.clone()),
+        line("Ended: ", result.end_reason.map(|reason| reason.to_string()).unwrap_or_else(|| String::from("-"))),
+        line("Source: ", result.source.clone().unwrap_or_else(|| String::from("-"))),
+        Spans::default(),
+        Spans::from(Span::styled("R: Retry | ENTER: New Test | ESC: Menu", label_style)),
+    ]
+}
+
+fn history_lines(history : &[TestResult]) -> Vec<Spans<'static>> {
+    let mut lines = vec![Spans::from(Span::styled("Recent Tests", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))];
+    for result in history.iter().rev().take(HISTORY_LEN) {
+        let content = format!("{:.0} WPM | {:.1}% Accuracy | {:.1}% Consistency | {}", result.net_wpm, result.accuracy, result.consistency, result.mode);
+        lines.push(Spans::from(Span::styled(content, Style::default().fg(Color::White))));
+    }
+
+    lines
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 	let popup_layout = Layout::default()
 		.direction(Direction::Vertical)
@@ -0,0 +1,92 @@
+use rand::Rng;
+
+/// Chance of a word being wrapped in quotes or parentheses
+const WRAP_RATE : f64 = 0.05;
+/// Chance of a word being followed by a comma
+const COMMA_RATE : f64 = 0.1;
+/// Chance of a word ending a sentence
+const SENTENCE_END_RATE : f64 = 0.08;
+
+/// Post-processing applied to generated words to make them read like prose
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers {
+    pub punctuation : bool, // capitalized sentences, commas, quotes...
+    pub number_rate : u64, // percentage of words replaced by numbers, 0 to disable
+}
+
+impl Modifiers {
+    /// Modifies a batch of words following the `previous` word, if any, so that
+    /// batches generated as a test goes read as one text, the last sentence
+    /// only being closed if `close` is set
+    pub fn apply<R : Rng>(&self, words : &[&str], previous : Option<&str>, close : bool, rng : &mut R) -> Vec<String> {
+        let number_rate = self.number_rate.min(100) as f64 / 100.0;
+        let mut sentence_start = previous.is_none_or(|word| word.ends_with(['.', '?', '!']));
+
+        let mut words : Vec<String> = words.iter().map(|&word| {
+            let mut word = if rng.gen_bool(number_rate) {
+                random_number(rng)
+            } else {
+                String::from(word)
+            };
+
+            if !self.punctuation {
+                return word;
+            }
+
+            if sentence_start {
+                word = capitalize(&word);
+            }
+
+            if rng.gen_bool(WRAP_RATE) {
+                word = match rng.gen_range(0..3) {
+                    0 => format!("\"{}\"", word),
+                    1 => format!("'{}'", word),
+                    _ => format!("({})", word),
+                };
+            }
+
+            sentence_start = rng.gen_bool(SENTENCE_END_RATE);
+            if sentence_start {
+                word.push(match rng.gen_range(0..10) {
+                    0 => '?',
+                    1 => '!',
+                    _ => '.',
+                });
+            } else if rng.gen_bool(COMMA_RATE) {
+                word.push(match rng.gen_range(0..10) {
+                    0 => ';',
+                    1 => ':',
+                    _ => ',',
+                });
+            }
+
+            word
+        }).collect();
+
+        // close the last sentence
+        if self.punctuation && close {
+            if let Some(last) = words.last_mut() {
+                let len = last.trim_end_matches([',', ';', ':']).len();
+                last.truncate(len);
+                if !last.ends_with(['.', '?', '!']) {
+                    last.push('.');
+                }
+            }
+        }
+
+        words
+    }
+}
+
+fn capitalize(word : &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn random_number<R : Rng>(rng : &mut R) -> String {
+    let digits = rng.gen_range(1..=4);
+    rng.gen_range(10u32.pow(digits - 1)..10u32.pow(digits)).to_string()
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+
+use rand::{
+    thread_rng,
+    seq::SliceRandom
+};
+
+/// Longest quote considered short (in characters)
+const SHORT_MAX : usize = 100;
+/// Longest quote considered medium (in characters)
+const MEDIUM_MAX : usize = 250;
+
+pub struct Quote {
+    pub text : &'static str,
+    pub source : &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteLength {
+    Short,
+    Medium,
+    Long,
+    Any,
+}
+
+impl QuoteLength {
+    pub const ALL : [QuoteLength; 4] = [QuoteLength::Short, QuoteLength::Medium, QuoteLength::Long, QuoteLength::Any];
+
+    pub fn matches(&self, quote : &Quote) -> bool {
+        let len = quote.text.chars().count();
+        match self {
+            QuoteLength::Short => len <= SHORT_MAX,
+            QuoteLength::Medium => len > SHORT_MAX && len <= MEDIUM_MAX,
+            QuoteLength::Long => len > MEDIUM_MAX,
+            QuoteLength::Any => true,
+        }
+    }
+}
+
+impl fmt::Display for QuoteLength {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            QuoteLength::Short => "short",
+            QuoteLength::Medium => "medium",
+            QuoteLength::Long => "long",
+            QuoteLength::Any => "any",
+        })
+    }
+}
+
+impl std::str::FromStr for QuoteLength {
+    type Err = String;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        QuoteLength::ALL.into_iter()
+            .find(|length| length.to_string() == s)
+            .ok_or(format!("unknown quote length '{}': expected short, medium, long or any", s))
+    }
+}
+
+/// Picks a random quote of the given length
+pub fn random_quote(length : QuoteLength) -> &'static Quote {
+    let quotes : Vec<&'static Quote> = QUOTES.iter().filter(|quote| length.matches(quote)).collect();
+    quotes.choose(&mut thread_rng()).expect("every quote length has at least one quote")
+}
+
+static QUOTES : [Quote; 24] = [
+    Quote {
+        text: "To be, or not to be, that is the question.",
+        source: "William Shakespeare, Hamlet",
+    },
+    Quote {
+        text: "All the world's a stage, and all the men and women merely players.",
+        source: "William Shakespeare, As You Like It",
+    },
+    Quote {
+        text: "Call me Ishmael.",
+        source: "Herman Melville, Moby-Dick",
+    },
+    Quote {
+        text: "I think, therefore I am.",
+        source: "Rene Descartes, Discourse on the Method",
+    },
+    Quote {
+        text: "The only thing we have to fear is fear itself.",
+        source: "Franklin D. Roosevelt, First Inaugural Address",
+    },
+    Quote {
+        text: "Not all those who wander are lost.",
+        source: "J. R. R. Tolkien, The Fellowship of the Ring",
+    },
+    Quote {
+        text: "Simplicity is prerequisite for reliability.",
+        source: "Edsger W. Dijkstra",
+    },
+    Quote {
+        text: "Premature optimization is the root of all evil.",
+        source: "Donald Knuth, Structured Programming with go to Statements",
+    },
+    Quote {
+        text: "It is a truth universally acknowledged, that a single man in possession of a good fortune, must be in want of a wife.",
+        source: "Jane Austen, Pride and Prejudice",
+    },
+    Quote {
+        text: "Happy families are all alike; every unhappy family is unhappy in its own way.",
+        source: "Leo Tolstoy, Anna Karenina",
+    },
+    Quote {
+        text: "I went to the woods because I wished to live deliberately, to front only the essential facts of life, and see if I could not learn what it had to teach, and not, when I came to die, discover that I had not lived.",
+        source: "Henry David Thoreau, Walden",
+    },
+    Quote {
+        text: "The reports of my death are greatly exaggerated.",
+        source: "Mark Twain",
+    },
+    Quote {
+        text: "Programs must be written for people to read, and only incidentally for machines to execute.",
+        source: "Harold Abelson, Structure and Interpretation of Computer Programs",
+    },
+    Quote {
+        text: "Any fool can write code that a computer can understand. Good programmers write code that humans can understand.",
+        source: "Martin Fowler, Refactoring",
+    },
+    Quote {
+        text: "Whether I shall turn out to be the hero of my own life, or whether that station will be held by anybody else, these pages must show.",
+        source: "Charles Dickens, David Copperfield",
+    },
+    Quote {
+        text: "In the beginning the Universe was created. This has made a lot of people very angry and been widely regarded as a bad move.",
+        source: "Douglas Adams, The Restaurant at the End of the Universe",
+    },
+    Quote {
+        text: "The sky above the port was the color of television, tuned to a dead channel.",
+        source: "William Gibson, Neuromancer",
+    },
+    Quote {
+        text: "It was a bright cold day in April, and the clocks were striking thirteen.",
+        source: "George Orwell, Nineteen Eighty-Four",
+    },
+    Quote {
+        text: "We hold these truths to be self-evident, that all men are created equal, that they are endowed by their Creator with certain unalienable Rights, that among these are Life, Liberty and the pursuit of Happiness.",
+        source: "United States Declaration of Independence",
+    },
+    Quote {
+        text: "It was the best of times, it was the worst of times, it was the age of wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch of incredulity, it was the season of Light, it was the season of Darkness, it was the spring of hope, it was the winter of despair.",
+        source: "Charles Dickens, A Tale of Two Cities",
+    },
+    Quote {
+        text: "Four score and seven years ago our fathers brought forth on this continent, a new nation, conceived in Liberty, and dedicated to the proposition that all men are created equal. Now we are engaged in a great civil war, testing whether that nation, or any nation so conceived and so dedicated, can long endure.",
+        source: "Abraham Lincoln, Gettysburg Address",
+    },
+    Quote {
+        text: "Some years ago, never mind how long precisely, having little or no money in my purse, and nothing particular to interest me on shore, I thought I would sail about a little and see the watery part of the world. It is a way I have of driving off the spleen and regulating the circulation.",
+        source: "Herman Melville, Moby-Dick",
+    },
+    Quote {
+        text: "Alice was beginning to get very tired of sitting by her sister on the bank, and of having nothing to do: once or twice she had peeped into the book her sister was reading, but it had no pictures or conversations in it, 'and what is the use of a book,' thought Alice, 'without pictures or conversations?'",
+        source: "Lewis Carroll, Alice's Adventures in Wonderland",
+    },
+    Quote {
+        text: "You don't know about me without you have read a book by the name of The Adventures of Tom Sawyer; but that ain't no matter. That book was made by Mr. Mark Twain, and he told the truth, mainly. There was things which he stretched, but mainly he told the truth.",
+        source: "Mark Twain, Adventures of Huckleberry Finn",
+    },
+];
@@ -0,0 +1,1173 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::clock::{Clock, SystemClock};
+use super::input::InputBuffer;
+use super::stats::{self, CharStats, Sample, TestResult};
+use super::text::{Separator, Word};
+
+/// Input events understood by a [`TypingSession`], independent of the terminal backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    ClearWord,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    Waiting, // no keystroke received yet
+    Running,
+    Finished,
+    Stopped, // aborted before finishing, no results are kept
+}
+
+/// What happens when a word isn't typed correctly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitPolicy {
+    Strict, // the word can't be submitted until it is correct
+    Lenient, // the word is submitted anyway, its mistakes counting as errors
+    StopOnLetter, // wrong characters aren't inserted at all
+}
+
+impl SubmitPolicy {
+    pub const ALL : [SubmitPolicy; 3] = [SubmitPolicy::Strict, SubmitPolicy::Lenient, SubmitPolicy::StopOnLetter];
+}
+
+impl fmt::Display for SubmitPolicy {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            SubmitPolicy::Strict => "strict",
+            SubmitPolicy::Lenient => "lenient",
+            SubmitPolicy::StopOnLetter => "stop on letter",
+        })
+    }
+}
+
+impl std::str::FromStr for SubmitPolicy {
+    type Err = String;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(SubmitPolicy::Strict),
+            "lenient" => Ok(SubmitPolicy::Lenient),
+            "stop-on-letter" => Ok(SubmitPolicy::StopOnLetter),
+            _ => Err(format!("unknown submit policy '{}': expected strict, lenient or stop-on-letter", s)),
+        }
+    }
+}
+
+/// How harshly mistakes are punished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Normal,
+    Expert, // submitting a word with an error fails the test
+    SuddenDeath, // any incorrect keystroke fails the test
+}
+
+impl Difficulty {
+    pub const ALL : [Difficulty; 3] = [Difficulty::Normal, Difficulty::Expert, Difficulty::SuddenDeath];
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Difficulty::Normal => "normal",
+            Difficulty::Expert => "expert",
+            Difficulty::SuddenDeath => "sudden death",
+        })
+    }
+}
+
+impl std::str::FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Difficulty::Normal),
+            "expert" => Ok(Difficulty::Expert),
+            "sudden-death" => Ok(Difficulty::SuddenDeath),
+            _ => Err(format!("unknown difficulty '{}': expected normal, expert or sudden-death", s)),
+        }
+    }
+}
+
+/// Why a test ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndReason {
+    TimeUp,
+    Completed, // every word was typed
+    Finished, // ended by the user
+    Mistake, // incorrect keystroke in sudden death
+    WordError, // word submitted with errors in expert
+}
+
+impl fmt::Display for EndReason {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            EndReason::TimeUp => "time is up",
+            EndReason::Completed => "all words typed",
+            EndReason::Finished => "finished",
+            EndReason::Mistake => "failed, incorrect keystroke (sudden death)",
+            EndReason::WordError => "failed, word submitted with errors (expert)",
+        })
+    }
+}
+
+/// What ends a test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Time(Duration), // countdown from the first keystroke
+    Words(usize), // number of words to type, the clock counts up
+    Text, // the whole text has to be typed, the clock counts up
+    Zen, // no target text, the test runs until it is finished explicitly
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Time(duration) => write!(f, "time {}s", duration.as_secs()),
+            Limit::Words(count) => write!(f, "words {}", count),
+            Limit::Text => write!(f, "text"),
+            Limit::Zen => write!(f, "zen"),
+        }
+    }
+}
+
+/// UI-independent typing test engine
+///
+/// The session owns the target words and the user's input for the current word.
+/// Front-ends translate their key events into [`Input`] values, call [`TypingSession::tick`]
+/// from their event loop and render from the accessors below.
+///
+/// Timing is derived from the instant of the first keystroke, so no background
+/// thread is needed to run the countdown. In untimed tests the last word is
+/// accepted as soon as it is typed correctly, without waiting for a space.
+/// All instants come from the session's [`Clock`], which lets tests drive it
+/// with a manual clock.
+///
+/// Zen sessions have no target words: every keystroke counts as correct and the
+/// typed words are recorded until [`TypingSession::finish`] is called.
+pub struct TypingSession<C : Clock = SystemClock> {
+    wordlist : Vec<Word>, // target words, the current one being at the index `typed.len()`
+    typed : Vec<Word>, // submitted words, as typed
+    input : InputBuffer, // current word as typed so far
+    char_count : usize,
+    char_stats : CharStats,
+    keystroke_times : Vec<Duration>, // time of every keystroke, from the start of the test
+    typed_chars : usize, // characters of submitted words, separators included
+    errors : usize, // uncorrected errors in submitted words
+    samples : Vec<Sample>,
+    sampled_keystrokes : usize, // keystrokes already accounted for in `samples`
+    sampled_errors : usize, // errors already accounted for in `samples`
+    state : SessionState,
+    limit : Limit,
+    policy : SubmitPolicy,
+    difficulty : Difficulty,
+    end_reason : Option<EndReason>,
+    backtrack : bool, // whether mistyped words can be reopened with backspace
+    pending : Option<(usize, usize)>, // position and keystrokes of a letter whose accent has yet to be typed
+    started : Option<Instant>,
+    ended : Option<Instant>,
+    clock : C,
+}
+
+impl TypingSession {
+    pub fn new(wordlist : Vec<Word>, limit : Limit) -> Self {
+        Self::with_clock(wordlist, limit, SystemClock)
+    }
+}
+
+impl<C : Clock> TypingSession<C> {
+    pub fn with_clock(mut wordlist : Vec<Word>, limit : Limit, clock : C) -> Self {
+        if let Limit::Words(count) = limit {
+            wordlist.truncate(count);
+        }
+        wordlist.iter_mut().for_each(normalize);
+
+        Self {
+            wordlist,
+            typed: vec![],
+            input: InputBuffer::default(),
+            char_count: 0,
+            char_stats: CharStats::default(),
+            keystroke_times: vec![],
+            typed_chars: 0,
+            errors: 0,
+            samples: vec![],
+            sampled_keystrokes: 0,
+            sampled_errors: 0,
+            state: SessionState::Waiting,
+            limit,
+            policy: SubmitPolicy::Strict,
+            difficulty: Difficulty::Normal,
+            end_reason: None,
+            backtrack: false,
+            pending: None,
+            started: None,
+            ended: None,
+            clock,
+        }
+    }
+
+    /// Appends target words, for tests whose words are generated as they go
+    pub fn extend<I : IntoIterator<Item = Word>>(&mut self, words : I) {
+        self.wordlist.extend(words.into_iter().map(|mut word| {
+            normalize(&mut word);
+            word
+        }));
+        if let Limit::Words(count) = self.limit {
+            self.wordlist.truncate(count);
+        }
+    }
+
+    /// Sets how mistyped words are handled, strict by default
+    pub fn with_policy(mut self, policy : SubmitPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets how harshly mistakes are punished, normal by default
+    pub fn with_difficulty(mut self, difficulty : Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    /// Allows backspacing into a previous word that was submitted with errors,
+    /// which only happens with the lenient policy
+    pub fn with_backtrack(mut self, backtrack : bool) -> Self {
+        self.backtrack = backtrack;
+        self
+    }
+
+    pub fn handle(&mut self, input : Input) {
+        if self.is_over() {
+            return;
+        }
+
+        // a letter waiting for its accent is wrong unless a combining mark follows
+        if self.pending.is_some() && !matches!(input, Input::Char(c) if is_combining_mark(c)) {
+            let removed = self.reject_pending();
+            // the rejected letter is already gone with the stop on letter policy
+            if self.is_over() || (removed && input == Input::Backspace) {
+                return;
+            }
+        }
+
+        match input {
+            Input::Backspace => {
+                if self.input.cursor() > 0 {
+                    self.erase(self.input.cursor() - 1);
+                } else if self.input.is_empty() && self.can_backtrack() {
+                    self.reopen();
+                }
+            },
+            Input::Delete => {
+                if self.input.len() > self.input.cursor() {
+                    self.erase(self.input.cursor());
+                }
+            },
+            Input::Left => self.input.move_left(),
+            Input::Right => self.input.move_right(),
+            Input::ClearWord => {
+                while !self.input.is_empty() {
+                    self.erase(self.input.len() - 1);
+                }
+            },
+            // Enter isn't part of the text between words on the same line
+            Input::Enter if !self.takes_enter() => {  },
+            Input::Enter => self.separate(input),
+            // spaces are part of the text within a line of code
+            Input::Char(' ') if !self.in_line() => self.separate(input),
+            Input::Char(c) => {
+                if self.state == SessionState::Waiting {
+                    self.started = Some(self.clock.now());
+                    self.state = SessionState::Running;
+                }
+
+                self.record_keystroke();
+                // a combining character completes the grapheme before it, so the
+                // keystrokes of a letter that is the start of the expected one once
+                // both are decomposed ("e" for "é") are only judged when the
+                // accent is typed, or when anything else is
+                let keystrokes = 1 + self.pending.take().map_or(0, |(_, keystrokes)| keystrokes);
+                let mut typed = self.input.clone();
+                typed.insert(c);
+                let pos = typed.cursor() - 1;
+                let expected = self.expected(pos);
+                let grapheme = typed.grapheme(pos);
+                let extra = expected.is_none();
+                let correct = self.is_zen() || (expected.is_some() && expected == grapheme);
+                if !correct && matches!((expected, grapheme), (Some(expected), Some(grapheme)) if is_partial(grapheme, expected)) {
+                    self.pending = Some((pos, keystrokes));
+                    self.input = typed;
+                    return;
+                }
+
+                if correct {
+                    self.char_stats.correct += keystrokes;
+                } else if extra {
+                    self.char_stats.extra += keystrokes;
+                } else {
+                    self.char_stats.incorrect += keystrokes;
+                }
+
+                if !correct {
+                    if self.difficulty == Difficulty::SuddenDeath {
+                        self.input = typed;
+                        self.finish_with(EndReason::Mistake);
+                        return;
+                    }
+                    if self.policy == SubmitPolicy::StopOnLetter {
+                        // the letter the wrong accent was typed on goes too
+                        if keystrokes > 1 {
+                            self.input.remove(pos);
+                        }
+                        return;
+                    }
+                }
+                self.input = typed;
+
+                if let Some(word) = self.current_word() {
+                    if self.time_limit().is_none() && self.typed.len() + 1 == self.wordlist.len() && self.input.as_str() == word.text {
+                        self.submit(word.separator, false);
+                    }
+                }
+            },
+        }
+    }
+
+    /// Counts the letter waiting for its accent as incorrect, ending the test in
+    /// sudden death and removing it with the stop on letter policy, in which case
+    /// true is returned
+    fn reject_pending(&mut self) -> bool {
+        let (pos, keystrokes) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return false,
+        };
+
+        self.char_stats.incorrect += keystrokes;
+        if self.difficulty == Difficulty::SuddenDeath {
+            self.finish_with(EndReason::Mistake);
+            false
+        } else if self.policy == SubmitPolicy::StopOnLetter {
+            self.input.remove(pos);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Accepts the current input as the typed version of the current word,
+    /// `separated` telling whether a separator was typed after it
+    fn submit(&mut self, separator : Separator, separated : bool) {
+        let input = self.input.take();
+        self.typed.push(Word { text: input, separator, indent: String::new() });
+
+        let (correct, errors, missed) = self.tally(self.typed.len() - 1);
+        self.char_count += correct;
+        self.errors += errors;
+        self.char_stats.missed += missed;
+        self.typed_chars += self.typed[self.typed.len() - 1].text.graphemes(true).count() + usize::from(separated);
+
+        if !self.is_zen() && self.typed.len() == self.wordlist.len() {
+            self.finish_with(EndReason::Completed);
+        }
+    }
+
+    /// Goes back to the previous word, undoing its submission
+    fn reopen(&mut self) {
+        let (correct, errors, missed) = self.tally(self.typed.len() - 1);
+        self.char_count -= correct;
+        self.errors -= errors;
+        self.char_stats.missed -= missed;
+
+        if let Some(word) = self.typed.pop() {
+            // only words submitted with a separator can be reopened, the last one ends the test
+            self.typed_chars -= word.text.graphemes(true).count() + 1;
+            self.input = InputBuffer::new(word.text);
+        }
+    }
+
+    /// Whether backspace can go back into the previous word, which has to be mistyped
+    fn can_backtrack(&self) -> bool {
+        if !self.backtrack || self.policy != SubmitPolicy::Lenient || self.is_zen() {
+            return false;
+        }
+
+        match (self.typed.last(), self.wordlist.get(self.typed.len().wrapping_sub(1))) {
+            (Some(typed), Some(word)) => typed.text != word.text,
+            _ => false,
+        }
+    }
+
+    /// Correct characters, uncorrected errors and missed characters of the submitted word at `index`
+    fn tally(&self, index : usize) -> (usize, usize, usize) {
+        let typed = &self.typed[index].text;
+        match self.wordlist.get(index) {
+            Some(word) => {
+                let correct = typed.graphemes(true)
+                    .zip(word.text.graphemes(true))
+                    .filter(|(typed, expected)| typed == expected)
+                    .count();
+                let missed = word.text.graphemes(true).count().saturating_sub(typed.graphemes(true).count());
+                (correct, stats::count_errors(typed, &word.text) + missed, missed)
+            },
+            // zen words have no target, everything typed is correct
+            None => (typed.graphemes(true).count(), 0, 0),
+        }
+    }
+
+    /// Handles a key typed at the end of a word, submitting it if it was typed
+    /// correctly (or at all, with the lenient policy)
+    fn separate(&mut self, input : Input) {
+        if self.is_zen() && self.input.is_empty() {
+            // an empty line in zen mode starts a new paragraph
+            if input == Input::Enter {
+                if let Some(word) = self.typed.last_mut() {
+                    word.separator = Separator::Newline;
+                }
+            }
+            return;
+        }
+
+        let correct = match self.current_word() {
+            Some(word) => self.input.as_str() == word.text,
+            None => self.is_zen(),
+        };
+        let separated = match self.current_word() {
+            Some(word) => match word.separator {
+                Separator::Space => input == Input::Char(' '),
+                Separator::Newline => true,
+                Separator::Line => input == Input::Enter,
+            },
+            None => self.is_zen(),
+        };
+        let accepted = separated && (correct || (self.policy == SubmitPolicy::Lenient && !self.input.is_empty()));
+        if self.state == SessionState::Running {
+            self.record_keystroke();
+            if accepted && correct {
+                self.char_stats.correct += 1;
+            } else {
+                self.char_stats.incorrect += 1;
+                if self.difficulty == Difficulty::SuddenDeath {
+                    self.finish_with(EndReason::Mistake);
+                    return;
+                }
+            }
+
+            if separated && !correct && !self.input.is_empty() && self.difficulty == Difficulty::Expert {
+                self.finish_with(EndReason::WordError);
+                return;
+            }
+        }
+
+        if accepted {
+            let separator = match self.current_word() {
+                Some(word) => word.separator,
+                None if input == Input::Enter => Separator::Newline,
+                None => Separator::Space,
+            };
+            self.submit(separator, true);
+        }
+    }
+
+    /// Target word currently being typed, none in zen mode or once every word was typed
+    fn current_word(&self) -> Option<&Word> {
+        self.wordlist.get(self.typed.len())
+    }
+
+    fn is_zen(&self) -> bool {
+        self.limit == Limit::Zen
+    }
+
+    /// Whether Enter is typed as part of the test, which is only the case at
+    /// the end of a paragraph or a line of code, and in zen mode
+    pub fn takes_enter(&self) -> bool {
+        match self.current_word() {
+            Some(word) => word.separator != Separator::Space,
+            None => self.is_zen(),
+        }
+    }
+
+    /// Whether the current word is a whole line, which only Enter can submit
+    fn in_line(&self) -> bool {
+        matches!(self.current_word(), Some(word) if word.separator == Separator::Line)
+    }
+
+    fn record_keystroke(&mut self) {
+        let elapsed = self.elapsed();
+        self.keystroke_times.push(elapsed);
+    }
+
+    /// Grapheme of the current word expected at position `pos` of the input
+    fn expected(&self, pos : usize) -> Option<&str> {
+        self.current_word().and_then(|word| word.text.graphemes(true).nth(pos))
+    }
+
+    /// Removes the input grapheme at `pos`, counting it as corrected if it was wrong
+    fn erase(&mut self, pos : usize) {
+        let removed = self.input.remove(pos);
+        if !self.is_zen() && self.expected(pos) != removed.as_deref() {
+            self.char_stats.corrected += 1;
+        }
+    }
+
+    /// Advances the countdown, sampling every elapsed second and finishing
+    /// the test once its deadline has passed
+    pub fn tick(&mut self) {
+        if self.state != SessionState::Running {
+            return;
+        }
+
+        self.sample();
+        if self.remaining() == Some(Duration::ZERO) {
+            self.finish_with(EndReason::TimeUp);
+        }
+    }
+
+    /// Records a sample for every whole second elapsed since the last one
+    fn sample(&mut self) {
+        let elapsed = self.elapsed();
+        loop {
+            let next = Duration::from_secs(self.samples.len() as u64 + 1);
+            if next > elapsed {
+                break;
+            }
+
+            self.push_sample(next, Duration::from_secs(1));
+        }
+    }
+
+    fn push_sample(&mut self, time : Duration, interval : Duration) {
+        let keystrokes = self.char_stats.keystrokes();
+        let errors = self.char_stats.incorrect + self.char_stats.extra;
+
+        self.samples.push(Sample {
+            time,
+            raw_wpm: stats::wpm(keystrokes - self.sampled_keystrokes, interval),
+            net_wpm: stats::net_wpm(self.typed_chars(), self.uncorrected_errors(), time),
+            errors: errors - self.sampled_errors,
+        });
+        self.sampled_keystrokes = keystrokes;
+        self.sampled_errors = errors;
+    }
+
+    /// Ends the test on the user's request
+    pub fn finish(&mut self) {
+        self.finish_with(EndReason::Finished);
+    }
+
+    /// Ends the test, counting the correctly typed prefix of the current word
+    fn finish_with(&mut self, reason : EndReason) {
+        if self.is_over() {
+            return;
+        }
+
+        self.end_reason = Some(reason);
+        if let Some((_, keystrokes)) = self.pending.take() {
+            self.char_stats.incorrect += keystrokes;
+        }
+
+        if self.state == SessionState::Running {
+            self.sample();
+
+            // account for the trailing fraction of a second
+            let elapsed = self.elapsed();
+            let sampled = Duration::from_secs(self.samples.len() as u64);
+            if elapsed > sampled {
+                self.push_sample(elapsed, elapsed - sampled);
+            }
+        }
+
+        if let Some(word) = self.wordlist.get(self.typed.len()) {
+            self.char_count += self.input.as_str().graphemes(true)
+                .zip(word.text.graphemes(true))
+                .take_while(|(typed, expected)| typed == expected)
+                .count();
+            self.typed_chars += self.input.len();
+            self.errors += stats::count_errors(self.input.as_str(), &word.text);
+        } else if self.is_zen() && !self.input.is_empty() {
+            self.char_count += self.input.len();
+            self.typed_chars += self.input.len();
+            self.typed.push(Word::new(self.input.as_str()));
+        }
+
+        self.input.take();
+        self.end(SessionState::Finished);
+    }
+
+    /// Aborts the test without keeping any progress
+    pub fn stop(&mut self) {
+        self.char_count = 0;
+        self.char_stats = CharStats::default();
+        self.typed_chars = 0;
+        self.errors = 0;
+        self.samples.clear();
+        self.keystroke_times.clear();
+        self.pending = None;
+        self.input.take();
+        self.end(SessionState::Stopped);
+    }
+
+    fn end(&mut self, state : SessionState) {
+        let now = self.clock.now();
+        // never count time past the deadline, even if the tick came in late
+        self.ended = Some(match (self.started, self.time_limit()) {
+            (Some(started), Some(duration)) => now.min(started + duration),
+            _ => now,
+        });
+        self.state = state;
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    /// Whether the test has finished or was stopped
+    pub fn is_over(&self) -> bool {
+        matches!(self.state, SessionState::Finished | SessionState::Stopped)
+    }
+
+    /// Time spent typing, frozen once the test is over
+    pub fn elapsed(&self) -> Duration {
+        match (self.started, self.ended) {
+            (Some(started), Some(ended)) => ended.saturating_duration_since(started),
+            (Some(started), None) => {
+                let elapsed = self.clock.now().saturating_duration_since(started);
+                match self.time_limit() {
+                    Some(duration) => elapsed.min(duration),
+                    None => elapsed,
+                }
+            },
+            (None, _) => Duration::ZERO,
+        }
+    }
+
+    /// Time left before the countdown expires, if the test is timed
+    pub fn remaining(&self) -> Option<Duration> {
+        let duration = self.time_limit()?;
+        if self.is_over() {
+            return Some(Duration::ZERO);
+        }
+
+        Some(duration.saturating_sub(self.elapsed()))
+    }
+
+    pub fn policy(&self) -> SubmitPolicy {
+        self.policy
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// Why the test ended, if it is finished
+    pub fn end_reason(&self) -> Option<EndReason> {
+        self.end_reason
+    }
+
+    pub fn limit(&self) -> Limit {
+        self.limit
+    }
+
+    fn time_limit(&self) -> Option<Duration> {
+        match self.limit {
+            Limit::Time(duration) => Some(duration),
+            Limit::Words(_) | Limit::Text | Limit::Zen => None,
+        }
+    }
+
+    /// Number of words accepted so far
+    pub fn submitted(&self) -> usize {
+        self.typed.len()
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Every target word of the test, including the ones already typed
+    pub fn wordlist(&self) -> &[Word] {
+        &self.wordlist
+    }
+
+    /// Target words left to type, starting with the current one
+    pub fn upcoming(&self) -> &[Word] {
+        self.wordlist.get(self.typed.len()..).unwrap_or(&[])
+    }
+
+    /// Words submitted so far, as they were typed
+    pub fn typed(&self) -> &[Word] {
+        &self.typed
+    }
+
+    pub fn word_input(&self) -> &str {
+        self.input.as_str()
+    }
+
+    pub fn input(&self) -> &InputBuffer {
+        &self.input
+    }
+
+    /// Position of the cursor in the input, in graphemes
+    pub fn cursor_pos(&self) -> usize {
+        self.input.cursor()
+    }
+
+    pub fn char_count(&self) -> usize {
+        self.char_count
+    }
+
+    /// Standardized word count (5 characters per word)
+    pub fn word_count(&self) -> usize {
+        self.char_count / 5
+    }
+
+    /// Characters typed so far, not counting the ones that were erased
+    pub fn typed_chars(&self) -> usize {
+        self.typed_chars + self.input.len()
+    }
+
+    /// Errors that are still present in the submitted words and the current input
+    pub fn uncorrected_errors(&self) -> usize {
+        match self.current_word() {
+            Some(word) => self.errors + stats::count_errors(self.input.as_str(), &word.text),
+            None => self.errors,
+        }
+    }
+
+    /// Time of every keystroke, from the start of the test
+    pub fn keystroke_times(&self) -> &[Duration] {
+        &self.keystroke_times
+    }
+
+    /// Steadiness of the keystroke rate, from 0 to 100%
+    pub fn consistency(&self) -> f64 {
+        stats::consistency(&self.keystroke_times, self.elapsed())
+    }
+
+    /// Per-second measurements taken so far
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    pub fn char_stats(&self) -> CharStats {
+        self.char_stats
+    }
+
+    /// Percentage of keystrokes that matched the expected character
+    pub fn accuracy(&self) -> f64 {
+        self.char_stats.accuracy()
+    }
+
+    /// Speed counting every keystroke, including mistakes that were corrected
+    pub fn raw_wpm(&self) -> f64 {
+        stats::wpm(self.char_stats.keystrokes(), self.elapsed())
+    }
+
+    /// Speed counting the characters currently typed, right or wrong
+    pub fn gross_wpm(&self) -> f64 {
+        stats::wpm(self.typed_chars(), self.elapsed())
+    }
+
+    /// Gross speed penalized by the uncorrected errors
+    pub fn net_wpm(&self) -> f64 {
+        stats::net_wpm(self.typed_chars(), self.uncorrected_errors(), self.elapsed())
+    }
+
+    /// Snapshot of the session's metrics
+    pub fn result(&self) -> TestResult {
+        TestResult {
+            net_wpm: self.net_wpm(),
+            raw_wpm: self.raw_wpm(),
+            gross_wpm: self.gross_wpm(),
+            accuracy: self.accuracy(),
+            char_stats: self.char_stats,
+            errors: self.uncorrected_errors(),
+            end_reason: self.end_reason,
+            elapsed: self.elapsed(),
+            mode: self.limit.to_string(),
+            source: None,
+            samples: self.samples.clone(),
+            consistency: self.consistency(),
+        }
+    }
+}
+
+/// Composes the characters of a target word, the form typed input is kept in
+fn normalize(word : &mut Word) {
+    word.text = word.text.nfc().collect();
+}
+
+/// Whether the typed grapheme is the expected one, or the start of it when both
+/// are decomposed into a base character and its combining marks
+fn is_partial(typed : &str, expected : &str) -> bool {
+    let mut expected = expected.nfd();
+    typed.nfd().all(|c| expected.next() == Some(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::typemaster::clock::ManualClock;
+
+    fn session(words : &[&str], limit : Limit) -> (TypingSession<Rc<ManualClock>>, Rc<ManualClock>) {
+        let clock = Rc::new(ManualClock::new());
+        let words = words.iter().map(|word| Word::new(word)).collect();
+        (TypingSession::with_clock(words, limit, clock.clone()), clock)
+    }
+
+    /// Types `keys`, letting `step` pass after each one
+    fn type_keys(session : &mut TypingSession<Rc<ManualClock>>, clock : &ManualClock, keys : &str, step : Duration) {
+        for c in keys.chars() {
+            session.handle(Input::Char(c));
+            clock.advance(step);
+        }
+    }
+
+    fn assert_close(actual : f64, expected : f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn countdown_starts_on_first_keystroke() {
+        let (mut session, clock) = session(&["hello", "world"], Limit::Time(Duration::from_secs(3)));
+        clock.advance(Duration::from_secs(5));
+        session.tick();
+        assert_eq!(session.state(), SessionState::Waiting);
+        assert_eq!(session.remaining(), Some(Duration::from_secs(3)));
+
+        session.handle(Input::Char('h'));
+        clock.advance(Duration::from_millis(2500));
+        session.tick();
+        assert_eq!(session.state(), SessionState::Running);
+        assert_eq!(session.remaining(), Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn countdown_expires_at_deadline() {
+        let (mut session, clock) = session(&["hello", "world"], Limit::Time(Duration::from_secs(3)));
+        session.handle(Input::Char('h'));
+        clock.advance(Duration::from_secs(3));
+        session.tick();
+
+        assert_eq!(session.state(), SessionState::Finished);
+        assert_eq!(session.end_reason(), Some(EndReason::TimeUp));
+        assert_eq!(session.remaining(), Some(Duration::ZERO));
+        assert_eq!(session.elapsed(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn late_tick_is_clamped_to_deadline() {
+        let (mut session, clock) = session(&["hello", "world"], Limit::Time(Duration::from_secs(3)));
+        session.handle(Input::Char('h'));
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(session.elapsed(), Duration::from_secs(3));
+
+        session.tick();
+        assert_eq!(session.end_reason(), Some(EndReason::TimeUp));
+        assert_eq!(session.elapsed(), Duration::from_secs(3));
+        assert_eq!(session.samples().len(), 3);
+        assert_eq!(session.samples().last().map(|sample| sample.time), Some(Duration::from_secs(3)));
+
+        // frozen once over
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(session.elapsed(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn samples_every_second_and_trailing_fraction() {
+        let (mut session, clock) = session(&["abcd"], Limit::Text);
+        type_keys(&mut session, &clock, "ab", Duration::from_millis(500));
+        session.tick();
+        assert_eq!(session.samples().len(), 1);
+
+        type_keys(&mut session, &clock, "x", Duration::from_millis(1500));
+        session.tick();
+        assert_eq!(session.samples().len(), 2);
+
+        session.finish();
+        let samples = session.samples();
+        assert_eq!(samples.iter().map(|sample| sample.time).collect::<Vec<_>>(), [
+            Duration::from_secs(1),
+            Duration::from_secs(2),
+            Duration::from_millis(2500),
+        ]);
+        // 2 keystrokes in the first second, 1 in the second and none after
+        assert_close(samples[0].raw_wpm, 24.0);
+        assert_close(samples[1].raw_wpm, 12.0);
+        assert_close(samples[2].raw_wpm, 0.0);
+        assert_eq!(samples.iter().map(|sample| sample.errors).collect::<Vec<_>>(), [0, 1, 0]);
+    }
+
+    #[test]
+    fn keystroke_times_from_first_keystroke() {
+        let (mut session, clock) = session(&["ab", "cd"], Limit::Text);
+        clock.advance(Duration::from_secs(1));
+        type_keys(&mut session, &clock, "ab", Duration::from_millis(200));
+        session.handle(Input::Backspace);
+        clock.advance(Duration::from_millis(300));
+        type_keys(&mut session, &clock, "b c", Duration::from_millis(100));
+
+        assert_eq!(session.keystroke_times(), [
+            Duration::ZERO,
+            Duration::from_millis(200),
+            Duration::from_millis(700),
+            Duration::from_millis(800),
+            Duration::from_millis(900),
+        ]);
+    }
+
+    #[test]
+    fn wpm_of_clean_words_test() {
+        let (mut session, clock) = session(&["ab", "cd"], Limit::Words(2));
+        type_keys(&mut session, &clock, "ab cd", Duration::from_millis(750));
+
+        assert_eq!(session.end_reason(), Some(EndReason::Completed));
+        assert_eq!(session.elapsed(), Duration::from_secs(3));
+        // 5 characters typed over 3 seconds, no separator after the last word
+        assert_eq!(session.typed_chars(), 5);
+        assert_close(session.raw_wpm(), 20.0);
+        assert_close(session.gross_wpm(), 20.0);
+        assert_close(session.net_wpm(), 20.0);
+    }
+
+    #[test]
+    fn wpm_with_uncorrected_error() {
+        let (session, clock) = session(&["abcde", "fghij"], Limit::Text);
+        let mut session = session.with_policy(SubmitPolicy::Lenient);
+        type_keys(&mut session, &clock, "abcdx fghij", Duration::from_millis(600));
+
+        assert_eq!(session.end_reason(), Some(EndReason::Completed));
+        assert_eq!(session.elapsed(), Duration::from_secs(6));
+        assert_eq!(session.uncorrected_errors(), 1);
+        // 11 keystrokes over a tenth of a minute, minus one word per error per minute
+        assert_close(session.raw_wpm(), 22.0);
+        assert_close(session.gross_wpm(), 22.0);
+        assert_close(session.net_wpm(), 12.0);
+        // the space submitting a mistyped word counts as incorrect too
+        assert_close(session.accuracy(), 900.0 / 11.0);
+    }
+
+    #[test]
+    fn enter_between_words_is_ignored() {
+        let (session, clock) = session(&["hello", "world"], Limit::Text);
+        let mut session = session.with_difficulty(Difficulty::SuddenDeath);
+        type_keys(&mut session, &clock, "he", Duration::from_millis(100));
+        assert!(!session.takes_enter());
+        session.handle(Input::Enter);
+
+        assert_eq!(session.state(), SessionState::Running);
+        assert_eq!(session.char_stats().incorrect, 0);
+        assert_eq!(session.keystroke_times().len(), 2);
+    }
+
+    #[test]
+    fn enter_ends_paragraph() {
+        let clock = Rc::new(ManualClock::new());
+        let words = vec![Word { separator: Separator::Newline, ..Word::new("end") }, Word::new("next")];
+        let mut session = TypingSession::with_clock(words, Limit::Text, clock.clone());
+        type_keys(&mut session, &clock, "end", Duration::from_millis(100));
+        assert!(session.takes_enter());
+        session.handle(Input::Enter);
+
+        assert_eq!(session.submitted(), 1);
+        assert_eq!(session.char_stats().correct, 4);
+    }
+
+    #[test]
+    fn combining_accent_matches_precomposed_target() {
+        let (mut session, clock) = session(&["caf\u{e9}", "next"], Limit::Text);
+        type_keys(&mut session, &clock, "cafe\u{301} ", Duration::from_millis(100));
+
+        assert_eq!(session.submitted(), 1);
+        assert_eq!(session.char_stats().incorrect, 0);
+        assert_eq!(session.typed()[0].text, "caf\u{e9}");
+    }
+
+    #[test]
+    fn bare_letter_for_accented_one_is_incorrect() {
+        let (mut session, clock) = session(&["f\u{fc}r", "next"], Limit::Text);
+        type_keys(&mut session, &clock, "fur ", Duration::from_millis(100));
+
+        assert_eq!(session.submitted(), 0);
+        assert_eq!(session.word_input(), "fur");
+        assert_eq!(session.char_stats().correct, 2);
+        assert_eq!(session.char_stats().incorrect, 2);
+    }
+
+    #[test]
+    fn bare_letter_for_accented_one_fails_sudden_death() {
+        let (session, clock) = session(&["f\u{fc}r", "next"], Limit::Text);
+        let mut session = session.with_difficulty(Difficulty::SuddenDeath);
+        type_keys(&mut session, &clock, "fu", Duration::from_millis(100));
+        assert_eq!(session.state(), SessionState::Running);
+
+        session.handle(Input::Char('r'));
+        assert_eq!(session.end_reason(), Some(EndReason::Mistake));
+        assert_eq!(session.char_stats().incorrect, 1);
+    }
+
+    #[test]
+    fn completed_accent_survives_sudden_death() {
+        let (session, clock) = session(&["f\u{fc}r", "next"], Limit::Text);
+        let mut session = session.with_difficulty(Difficulty::SuddenDeath);
+        type_keys(&mut session, &clock, "fu\u{308}r ", Duration::from_millis(100));
+        assert_eq!(session.state(), SessionState::Running);
+        assert_eq!(session.submitted(), 1);
+        assert_eq!(session.char_stats().incorrect, 0);
+    }
+
+    #[test]
+    fn bare_letter_for_accented_one_stops_on_letter() {
+        let (session, clock) = session(&["f\u{fc}r", "next"], Limit::Text);
+        let mut session = session.with_policy(SubmitPolicy::StopOnLetter);
+        type_keys(&mut session, &clock, "fur", Duration::from_millis(100));
+        assert_eq!(session.word_input(), "f");
+        assert_eq!(session.char_stats().incorrect, 2);
+
+        // backspace only takes the rejected letter back
+        type_keys(&mut session, &clock, "u", Duration::from_millis(100));
+        session.handle(Input::Backspace);
+        assert_eq!(session.word_input(), "f");
+
+        type_keys(&mut session, &clock, "u\u{308}r ", Duration::from_millis(100));
+        assert_eq!(session.submitted(), 1);
+        assert_eq!(session.char_stats().incorrect, 3);
+    }
+
+    #[test]
+    fn decomposed_target_matches_precomposed_input() {
+        let (mut session, clock) = session(&["cafe\u{301}", "next"], Limit::Text);
+        type_keys(&mut session, &clock, "caf\u{e9} ", Duration::from_millis(100));
+
+        assert_eq!(session.submitted(), 1);
+        assert_eq!(session.char_stats().incorrect, 0);
+    }
+
+    #[test]
+    fn strict_policy_holds_mistyped_word() {
+        let (mut session, clock) = session(&["abc", "def"], Limit::Text);
+        type_keys(&mut session, &clock, "abx ", Duration::from_millis(100));
+
+        assert_eq!(session.submitted(), 0);
+        assert_eq!(session.word_input(), "abx");
+        assert_eq!(session.char_stats().incorrect, 2);
+    }
+
+    #[test]
+    fn stop_on_letter_policy_rejects_wrong_letter() {
+        let (session, clock) = session(&["abc", "def"], Limit::Text);
+        let mut session = session.with_policy(SubmitPolicy::StopOnLetter);
+        type_keys(&mut session, &clock, "abxc ", Duration::from_millis(100));
+
+        assert_eq!(session.submitted(), 1);
+        assert_eq!(session.typed()[0].text, "abc");
+        assert_eq!(session.char_stats().incorrect, 1);
+        assert_eq!(session.uncorrected_errors(), 0);
+    }
+
+    #[test]
+    fn lenient_policy_submits_mistyped_word() {
+        let (session, clock) = session(&["abc", "def"], Limit::Text);
+        let mut session = session.with_policy(SubmitPolicy::Lenient);
+        type_keys(&mut session, &clock, "ab ", Duration::from_millis(100));
+
+        assert_eq!(session.submitted(), 1);
+        // the missed character is an error
+        assert_eq!(session.char_stats().missed, 1);
+        assert_eq!(session.uncorrected_errors(), 1);
+        assert_eq!(session.typed_chars(), 3);
+    }
+
+    #[test]
+    fn backspace_reopens_mistyped_word() {
+        let (session, clock) = session(&["abc", "def"], Limit::Text);
+        let mut session = session.with_policy(SubmitPolicy::Lenient).with_backtrack(true);
+        type_keys(&mut session, &clock, "abx ", Duration::from_millis(500));
+        assert_eq!((session.submitted(), session.uncorrected_errors(), session.typed_chars()), (1, 1, 4));
+
+        session.handle(Input::Backspace);
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(session.submitted(), 0);
+        assert_eq!(session.word_input(), "abx");
+        assert_eq!((session.uncorrected_errors(), session.typed_chars()), (1, 3));
+
+        session.handle(Input::Backspace);
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(session.char_stats().corrected, 1);
+        assert_eq!(session.uncorrected_errors(), 0);
+
+        type_keys(&mut session, &clock, "c ", Duration::from_millis(500));
+        assert_eq!((session.submitted(), session.uncorrected_errors(), session.typed_chars()), (1, 0, 4));
+        assert_eq!(session.char_count(), 3);
+
+        type_keys(&mut session, &clock, "def", Duration::from_millis(500));
+        assert_eq!(session.end_reason(), Some(EndReason::Completed));
+        assert_eq!(session.elapsed(), Duration::from_secs(5));
+        assert_eq!(session.char_count(), 6);
+        // 7 characters left over 5 seconds, the 9 keystrokes counting for the raw speed
+        assert_close(session.net_wpm(), 16.8);
+        assert_close(session.gross_wpm(), 16.8);
+        assert_close(session.raw_wpm(), 21.6);
+    }
+
+    #[test]
+    fn backspace_stays_out_of_correct_word() {
+        let (session, clock) = session(&["abc", "def"], Limit::Text);
+        let mut session = session.with_policy(SubmitPolicy::Lenient).with_backtrack(true);
+        type_keys(&mut session, &clock, "abc ", Duration::from_millis(100));
+        session.handle(Input::Backspace);
+
+        assert_eq!(session.submitted(), 1);
+        assert_eq!(session.word_input(), "");
+    }
+
+    #[test]
+    fn backspace_stays_out_of_word_without_backtrack() {
+        let (session, clock) = session(&["abc", "def"], Limit::Text);
+        let mut session = session.with_policy(SubmitPolicy::Lenient).with_backtrack(false);
+        type_keys(&mut session, &clock, "abx ", Duration::from_millis(100));
+        session.handle(Input::Backspace);
+
+        assert_eq!(session.submitted(), 1);
+        assert_eq!(session.uncorrected_errors(), 1);
+    }
+
+    #[test]
+    fn expert_fails_on_mistyped_word() {
+        let (session, clock) = session(&["abc", "def", "ghi"], Limit::Text);
+        let mut session = session.with_policy(SubmitPolicy::Lenient).with_difficulty(Difficulty::Expert);
+        type_keys(&mut session, &clock, "abx", Duration::from_millis(100));
+        assert_eq!(session.state(), SessionState::Running);
+
+        // a wrong letter that was corrected doesn't count
+        session.handle(Input::Backspace);
+        type_keys(&mut session, &clock, "c dex ", Duration::from_millis(100));
+        assert_eq!(session.submitted(), 1);
+        assert_eq!(session.end_reason(), Some(EndReason::WordError));
+    }
+
+    #[test]
+    fn sudden_death_fails_on_wrong_keystroke() {
+        let (session, clock) = session(&["abc", "def"], Limit::Text);
+        let mut session = session.with_difficulty(Difficulty::SuddenDeath);
+        type_keys(&mut session, &clock, "abx", Duration::from_millis(100));
+
+        assert_eq!(session.end_reason(), Some(EndReason::Mistake));
+        assert_eq!(session.char_stats().incorrect, 1);
+        session.handle(Input::Char('c'));
+        assert_eq!(session.char_stats().correct, 2);
+    }
+}
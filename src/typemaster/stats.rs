@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::session::EndReason;
+
+/// Number of characters that make up a standardized word
+pub const CHARS_PER_WORD : f64 = 5.0;
+
+/// Per-keystroke tally of how characters were typed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CharStats {
+    pub correct : usize, // keystrokes matching the expected character
+    pub incorrect : usize, // keystrokes not matching the expected character
+    pub extra : usize, // keystrokes past the end of the expected word
+    pub corrected : usize, // incorrect or extra characters that were erased afterwards
+    pub missed : usize, // characters of submitted words that were never typed
+}
+
+impl CharStats {
+    /// Every keystroke that produced a character
+    pub fn keystrokes(&self) -> usize {
+        self.correct + self.incorrect + self.extra
+    }
+
+    /// Percentage of keystrokes that matched the expected character
+    pub fn accuracy(&self) -> f64 {
+        let keystrokes = self.keystrokes();
+        if keystrokes == 0 {
+            return 100.0;
+        }
+
+        self.correct as f64 * 100.0 / keystrokes as f64
+    }
+}
+
+/// Speed and errors measured over one second of a test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub time : Duration, // end of the sampled interval, from the start of the test
+    pub raw_wpm : f64, // keystroke rate within the interval
+    pub net_wpm : f64, // net speed of the whole test up to `time`
+    pub errors : usize, // incorrect and extra keystrokes within the interval
+}
+
+/// Summary of a finished test
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub net_wpm : f64,
+    pub raw_wpm : f64,
+    pub gross_wpm : f64,
+    pub accuracy : f64,
+    pub char_stats : CharStats,
+    pub errors : usize, // uncorrected errors left in the typed text
+    pub end_reason : Option<EndReason>, // unset if the test is still running
+    pub elapsed : Duration,
+    pub mode : String,
+    pub source : Option<String>, // attribution of the typed text, if any
+    pub samples : Vec<Sample>,
+    pub consistency : f64,
+}
+
+/// Words per minute for `chars` characters typed over `elapsed`
+pub fn wpm(chars : usize, elapsed : Duration) -> f64 {
+    let minutes = elapsed.as_secs_f64() / 60.0;
+    if minutes <= 0.0 {
+        return 0.0;
+    }
+
+    chars as f64 / CHARS_PER_WORD / minutes
+}
+
+/// Gross WPM minus one word per uncorrected error per minute, never below zero
+pub fn net_wpm(chars : usize, errors : usize, elapsed : Duration) -> f64 {
+    let minutes = elapsed.as_secs_f64() / 60.0;
+    if minutes <= 0.0 {
+        return 0.0;
+    }
+
+    (wpm(chars, elapsed) - errors as f64 / minutes).max(0.0)
+}
+
+/// Number of positions where `typed` differs from `expected`, including
+/// characters typed past the end of the expected word
+pub fn count_errors(typed : &str, expected : &str) -> usize {
+    let mismatched = typed.graphemes(true)
+        .zip(expected.graphemes(true))
+        .filter(|(typed, expected)| typed != expected)
+        .count();
+    let typed_len = typed.graphemes(true).count();
+    let expected_len = expected.graphemes(true).count();
+
+    mismatched + typed_len.saturating_sub(expected_len)
+}
+
+/// Steadiness of the typing speed, from 0 to 100%
+///
+/// Keystrokes are bucketed per second of the test and the score is one minus
+/// the coefficient of variation of the keystroke rates of those buckets, so a
+/// typist who bursts and pauses scores lower than one who keeps a steady pace.
+/// The trailing fraction of a second is a shorter bucket, weighted by its length.
+pub fn consistency(keystroke_times : &[Duration], elapsed : Duration) -> f64 {
+    let seconds = elapsed.as_secs_f64();
+    if seconds <= 0.0 {
+        return 0.0;
+    }
+
+    let buckets = seconds.ceil() as usize;
+    let mut counts = vec![0usize; buckets];
+    for time in keystroke_times {
+        // a keystroke landing right on the end still belongs to the test
+        counts[(time.as_secs() as usize).min(buckets - 1)] += 1;
+    }
+
+    let mean = counts.iter().sum::<usize>() as f64 / seconds;
+    if mean == 0.0 {
+        return 0.0;
+    }
+
+    let variance = counts.iter().enumerate().map(|(second, &count)| {
+        let length = (seconds - second as f64).min(1.0);
+        length * (count as f64 / length - mean).powi(2)
+    }).sum::<f64>() / seconds;
+    let variation = variance.sqrt() / mean;
+
+    ((1.0 - variation) * 100.0).clamp(0.0, 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `count` keystrokes evenly spread over `[start, end)`, in milliseconds
+    fn keystrokes(start : u64, end : u64, count : u64) -> Vec<Duration> {
+        (0..count).map(|i| Duration::from_millis(start + i * (end - start) / count)).collect()
+    }
+
+    #[test]
+    fn consistency_of_steady_pace_with_partial_second() {
+        let times = keystrokes(0, 3500, 35);
+        assert_eq!(consistency(&times, Duration::from_millis(3500)), 100.0);
+
+        // a short test is a single partial second
+        let times = keystrokes(0, 500, 5);
+        assert_eq!(consistency(&times, Duration::from_millis(500)), 100.0);
+    }
+
+    #[test]
+    fn consistency_counts_keystrokes_of_last_fraction() {
+        // a burst in the trailing half second makes the pace uneven
+        let mut times = keystrokes(0, 2000, 10);
+        times.extend(keystrokes(2000, 2500, 10));
+        assert!(consistency(&times, Duration::from_millis(2500)) < 50.0);
+    }
+
+    #[test]
+    fn consistency_without_keystrokes() {
+        assert_eq!(consistency(&[], Duration::from_secs(3)), 0.0);
+        assert_eq!(consistency(&[], Duration::ZERO), 0.0);
+    }
+}
@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::{self, Read};
+
+/// What has to be typed after a word to move on to the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    Space,
+    Newline, // end of a paragraph, Enter or space
+    Line, // end of a line of code, Enter only, spaces being part of the line
+}
+
+/// How leading whitespace of code is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Auto, // shown but not typed
+    Required, // typed like any other character
+}
+
+/// Target word of a typing test
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    pub text : String,
+    pub separator : Separator,
+    pub indent : String, // leading whitespace shown before the word, but not typed
+}
+
+impl Word {
+    pub fn new(text : &str) -> Self {
+        Self { text: String::from(text), separator: Separator::Space, indent: String::new() }
+    }
+}
+
+/// Source file used in code mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeFile {
+    pub path : String,
+    pub contents : String,
+}
+
+/// Splits a text into words, keeping a paragraph break after the last word of
+/// every paragraph (paragraphs are separated by blank lines)
+pub fn parse_paragraphs(text : &str) -> Vec<Word> {
+    let mut words : Vec<Word> = vec![];
+    let mut paragraph_start = 0;
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if let Some(word) = words[paragraph_start..].last_mut() {
+                word.separator = Separator::Newline;
+            }
+            paragraph_start = words.len();
+            continue;
+        }
+
+        words.extend(line.split_whitespace().map(Word::new));
+    }
+
+    words
+}
+
+/// Splits source code into lines to be typed one at a time, skipping blank lines
+pub fn parse_code(code : &str, indent : Indent) -> Vec<Word> {
+    code.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let text = line.trim_start();
+            let leading = &line[..line.len() - text.len()];
+            match indent {
+                Indent::Auto => Word { text: String::from(text), separator: Separator::Line, indent: String::from(leading) },
+                Indent::Required => Word { text: String::from(line), separator: Separator::Line, indent: String::new() },
+            }
+        })
+        .collect()
+}
+
+/// Reads the text of a custom test from a file, or from stdin if `path` is "-"
+pub fn read_text(path : &str) -> Result<String, String> {
+    let text = if path == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).map(|_| text)
+    } else {
+        fs::read_to_string(path)
+    };
+
+    let text = text.map_err(|err| format!("failed to read '{}': {}", path, err))?;
+    if text.split_whitespace().next().is_none() {
+        return Err(format!("'{}' does not contain any text", path));
+    }
+
+    Ok(text)
+}
@@ -1,8 +1,175 @@
-pub fn get_wordlist() -> Vec<&'static str> {
-    WORDLIST.to_vec()
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rand::{Rng, seq::SliceRandom};
+use serde_json::Value;
+
+/// Name of the language used unless another one is chosen
+pub const DEFAULT_LANGUAGE : &str = "english";
+
+/// Languages compiled into the binary besides English, one word per line
+static BUNDLED : [(&str, &str); 4] = [
+    ("spanish", include_str!("languages/spanish.txt")),
+    ("french", include_str!("languages/french.txt")),
+    ("german", include_str!("languages/german.txt")),
+    ("russian", include_str!("languages/russian.txt")),
+];
+
+/// Words that tests in a language are generated from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wordlist {
+    pub name : String,
+    pub words : Vec<String>,
+}
+
+impl Wordlist {
+    fn new<'a, I : IntoIterator<Item = &'a str>>(name : &str, words : I) -> Self {
+        Self { name: String::from(name), words: words.into_iter().map(String::from).collect() }
+    }
+
+    /// Languages compiled into the binary, the default one first
+    pub fn bundled() -> Vec<Self> {
+        let mut wordlists = vec![Self::new(DEFAULT_LANGUAGE, ENGLISH.iter().copied())];
+        wordlists.extend(BUNDLED.iter().map(|(name, words)| Self::new(name, words.lines())));
+        wordlists
+    }
+
+    /// Reads a wordlist file, either plain text with one word per line or a JSON
+    /// array of words, naming the language after the file
+    pub fn load(path : &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| format!("failed to read '{}': {}", path.display(), err))?;
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+        let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => parse_json(&contents, stem),
+            _ => parse_lines(&contents, stem),
+        };
+
+        parsed.map_err(|err| format!("invalid wordlist '{}': {}", path.display(), err))
+    }
+
+    /// Loads the `.txt` and `.json` wordlists found in the user directory,
+    /// along with the name and error of every one that couldn't be loaded
+    pub fn load_user() -> (Vec<Self>, Vec<(String, String)>) {
+        let mut wordlists : Vec<Self> = Vec::new();
+        let mut errors = Vec::new();
+        let dir = match user_dir() {
+            Some(dir) if dir.is_dir() => dir,
+            _ => return (wordlists, errors),
+        };
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                errors.push((String::new(), format!("failed to read '{}': {}", dir.display(), err)));
+                return (wordlists, errors);
+            },
+        };
+        let mut paths : Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && matches!(path.extension().and_then(|ext| ext.to_str()), Some("txt" | "json")))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match Self::load(&path) {
+                Ok(wordlist) if wordlists.iter().any(|known| known.name == wordlist.name) => {
+                    let err = format!("invalid wordlist '{}': another wordlist in '{}' is already named '{}'", path.display(), dir.display(), wordlist.name);
+                    errors.push((wordlist.name, err));
+                },
+                Ok(wordlist) => wordlists.push(wordlist),
+                Err(err) => {
+                    let name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+                    errors.push((String::from(name), err));
+                },
+            }
+        }
+
+        (wordlists, errors)
+    }
+
+    /// Picks `count` distinct random words, or all of them in a random order if there are fewer
+    pub fn random_words<R : Rng>(&self, count : usize, rng : &mut R) -> Vec<&str> {
+        self.words.choose_multiple(rng, count).map(String::as_str).collect()
+    }
+}
+
+/// Directory user wordlists are read from, inside the configuration directory
+/// (`$XDG_CONFIG_HOME`, `~/.config` or `%APPDATA%`)
+pub fn user_dir() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(config.join("typemaster").join("wordlists"))
+}
+
+/// Parses a plain text wordlist, one word per line
+fn parse_lines(contents : &str, name : &str) -> Result<Wordlist, String> {
+    let mut lines : Vec<&str> = contents.lines().map(str::trim).collect();
+    // trailing blank lines are left by editors, not a mistake
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    validate(&lines, |index| format!("line {}", index + 1))?;
+    Ok(Wordlist::new(name, lines))
 }
 
-const WORDLIST : [&'static str; 9885] = [
+/// Parses a JSON wordlist, either an array of words or an object with a
+/// `words` array and an optional `name`
+fn parse_json(contents : &str, name : &str) -> Result<Wordlist, String> {
+    let value : Value = serde_json::from_str(contents).map_err(|err| format!("malformed JSON: {}", err))?;
+    let (name, words) = match &value {
+        Value::Array(words) => (name, words),
+        Value::Object(object) => {
+            let name = match object.get("name") {
+                Some(Value::String(name)) if !name.trim().is_empty() => name.trim(),
+                Some(_) => return Err(String::from("\"name\" must be a non-empty string")),
+                None => name,
+            };
+            match object.get("words") {
+                Some(Value::Array(words)) => (name, words),
+                _ => return Err(String::from("expected a \"words\" array")),
+            }
+        },
+        _ => return Err(String::from("expected an array of words or an object with a \"words\" array")),
+    };
+
+    let words = words.iter().enumerate().map(|(index, word)| match word {
+        Value::String(word) => Ok(word.as_str()),
+        _ => Err(format!("word {} is not a string", index + 1)),
+    }).collect::<Result<Vec<&str>, String>>()?;
+
+    validate(&words, |index| format!("word {}", index + 1))?;
+    Ok(Wordlist::new(name, words))
+}
+
+/// Rejects empty words, words with whitespace or control characters and
+/// duplicates, `locate` naming the position of a word in the file
+fn validate<F : Fn(usize) -> String>(words : &[&str], locate : F) -> Result<(), String> {
+    if words.is_empty() {
+        return Err(String::from("does not contain any words"));
+    }
+
+    let mut seen : HashMap<&str, usize> = HashMap::new();
+    for (index, &word) in words.iter().enumerate() {
+        if word.is_empty() {
+            return Err(format!("{} is empty", locate(index)));
+        }
+        if let Some(c) = word.chars().find(|c| c.is_whitespace() || c.is_control()) {
+            return Err(format!("{}: '{}' contains the invalid character {:?}", locate(index), word.escape_debug(), c));
+        }
+        if let Some(&first) = seen.get(word) {
+            return Err(format!("{}: '{}' is a duplicate of {}", locate(index), word, locate(first)));
+        }
+        seen.insert(word, index);
+    }
+
+    Ok(())
+}
+
+static ENGLISH : [&str; 9885] = [
     "the",
     "of",
     "and",
@@ -9889,3 +10056,68 @@ const WORDLIST : [&'static str; 9885] = [
     "configurations",
     "poison"
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(wordlist : &Wordlist) -> Vec<&str> {
+        wordlist.words.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn plain_text_words() {
+        let wordlist = parse_lines("alpha\n  beta \r\ngamma\n\n\n", "greek").unwrap();
+        assert_eq!(wordlist.name, "greek");
+        assert_eq!(words(&wordlist), ["alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn plain_text_errors() {
+        assert_eq!(parse_lines("", "empty").unwrap_err(), "does not contain any words");
+        assert_eq!(parse_lines("\n \n", "empty").unwrap_err(), "does not contain any words");
+        assert_eq!(parse_lines("alpha\n\nbeta\n", "greek").unwrap_err(), "line 2 is empty");
+        assert_eq!(parse_lines("alpha\nbeta\nalpha\n", "greek").unwrap_err(), "line 3: 'alpha' is a duplicate of line 1");
+        assert_eq!(parse_lines("alpha\nice cream\n", "greek").unwrap_err(), "line 2: 'ice cream' contains the invalid character ' '");
+        assert_eq!(parse_lines("al\u{7}pha\n", "greek").unwrap_err(), "line 1: 'al\\u{7}pha' contains the invalid character '\\u{7}'");
+    }
+
+    #[test]
+    fn json_words() {
+        let wordlist = parse_json(r#"["red", "green"]"#, "colors").unwrap();
+        assert_eq!(wordlist.name, "colors");
+        assert_eq!(words(&wordlist), ["red", "green"]);
+
+        let wordlist = parse_json(r#"{"name": "colours", "words": ["red", "green"]}"#, "colors").unwrap();
+        assert_eq!(wordlist.name, "colours");
+        assert_eq!(words(&wordlist), ["red", "green"]);
+
+        let wordlist = parse_json(r#"{"words": ["red"]}"#, "colors").unwrap();
+        assert_eq!(wordlist.name, "colors");
+    }
+
+    #[test]
+    fn json_errors() {
+        assert_eq!(parse_json("[]", "colors").unwrap_err(), "does not contain any words");
+        assert_eq!(parse_json(r#"["red", ""]"#, "colors").unwrap_err(), "word 2 is empty");
+        assert_eq!(parse_json(r#"["red", "blue", "red"]"#, "colors").unwrap_err(), "word 3: 'red' is a duplicate of word 1");
+        assert_eq!(parse_json(r#"["red", "sky blue"]"#, "colors").unwrap_err(), "word 2: 'sky blue' contains the invalid character ' '");
+        assert_eq!(parse_json(r#"["red\tblue"]"#, "colors").unwrap_err(), "word 1: 'red\\tblue' contains the invalid character '\\t'");
+        assert_eq!(parse_json(r#"["red", 3]"#, "colors").unwrap_err(), "word 2 is not a string");
+        assert_eq!(parse_json(r#"{"name": "", "words": ["red"]}"#, "colors").unwrap_err(), "\"name\" must be a non-empty string");
+        assert_eq!(parse_json(r#"{"name": 3, "words": ["red"]}"#, "colors").unwrap_err(), "\"name\" must be a non-empty string");
+        assert_eq!(parse_json(r#"{"name": "colors"}"#, "colors").unwrap_err(), "expected a \"words\" array");
+        assert_eq!(parse_json(r#""red""#, "colors").unwrap_err(), "expected an array of words or an object with a \"words\" array");
+        assert!(parse_json("[\"red\"", "colors").unwrap_err().starts_with("malformed JSON: "));
+    }
+
+    #[test]
+    fn load_names_file_in_errors() {
+        let path = env::temp_dir().join(format!("typemaster-{}-broken.txt", std::process::id()));
+        fs::write(&path, "alpha\nalpha\n").unwrap();
+        let err = Wordlist::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(err, format!("invalid wordlist '{}': line 2: 'alpha' is a duplicate of line 1", path.display()));
+    }
+}